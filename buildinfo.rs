@@ -0,0 +1,99 @@
+use crate::manifest::{self, Manifest};
+use crate::properties;
+use std::fmt;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+// Where git-commit-id-plugin and the Gradle git-properties plugin leave their
+// output: the classpath root, which Spring Boot and WAR packaging relocate
+const GIT_PROPERTIES_PATHS: &[&str] = &[
+    "git.properties",
+    "META-INF/git.properties",
+    "BOOT-INF/classes/git.properties",
+    "WEB-INF/classes/git.properties",
+];
+
+/// Where a JAR came from: the commit, branch and build time recorded by the
+/// build, from `git.properties` or else the manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildInfo {
+    // Entry the details were read from
+    pub source: String,
+    pub commit: Option<String>,
+    pub branch: Option<String>,
+    pub dirty: bool,
+    pub build_time: Option<String>,
+    pub build_jdk: Option<String>,
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(commit) = &self.commit {
+            parts.push(format!("commit {}{}", commit, if self.dirty { " (dirty)" } else { "" }));
+        }
+        if let Some(branch) = &self.branch {
+            parts.push(format!("branch {}", branch));
+        }
+        if let Some(time) = &self.build_time {
+            parts.push(format!("built {}", time));
+        }
+        if let Some(jdk) = &self.build_jdk {
+            parts.push(format!("JDK {}", jdk));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl BuildInfo {
+    /// Reads the `git.properties` keys written by git-commit-id-plugin
+    /// (`git.commit.id[.abbrev]`, `git.branch`, `git.build.time`, `git.dirty`).
+    pub fn from_git_properties(text: &str, source: &str) -> Option<BuildInfo> {
+        let mut info = BuildInfo { source: source.to_string(), ..Default::default() };
+        let mut full_commit = None;
+
+        for property in properties::parse(text) {
+            let value = Some(property.value).filter(|v| !v.is_empty());
+            match property.key.as_str() {
+                "git.commit.id.abbrev" => info.commit = value,
+                "git.commit.id" | "git.commit.id.full" => full_commit = value,
+                "git.branch" => info.branch = value,
+                "git.build.time" => info.build_time = value,
+                // Older plugin versions only record the commit time
+                "git.commit.time" if info.build_time.is_none() => info.build_time = value,
+                "git.dirty" => info.dirty = value.is_some_and(|v| v == "true"),
+                _ => {}
+            }
+        }
+        info.commit = info.commit.or(full_commit);
+
+        (info.commit.is_some() || info.branch.is_some()).then_some(info)
+    }
+
+    /// Falls back to the `Build-Jdk`/`Build-Jdk-Spec` and `Build-Time`
+    /// attributes that Maven and Gradle plugins write to the manifest.
+    pub fn from_manifest(manifest: &Manifest) -> Option<BuildInfo> {
+        let info = BuildInfo {
+            source: manifest::MANIFEST_PATH.to_string(),
+            build_time: manifest.get("Build-Time").map(str::to_string),
+            build_jdk: manifest.get("Build-Jdk").or_else(|| manifest.get("Build-Jdk-Spec")).map(str::to_string),
+            ..Default::default()
+        };
+        (info.build_time.is_some() || info.build_jdk.is_some()).then_some(info)
+    }
+
+    pub fn read_from_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<BuildInfo> {
+        for path in GIT_PROPERTIES_PATHS {
+            let Ok(mut entry) = archive.by_name(path) else { continue };
+            let mut bytes = Vec::new();
+            if entry.read_to_end(&mut bytes).is_err() {
+                continue;
+            }
+            if let Some(info) = BuildInfo::from_git_properties(&String::from_utf8_lossy(&bytes), path) {
+                return Some(info);
+            }
+        }
+
+        Manifest::read_from_archive(archive).and_then(|manifest| BuildInfo::from_manifest(&manifest))
+    }
+}
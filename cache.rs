@@ -0,0 +1,110 @@
+use crate::JarToolError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+const INDEX_FILE: &str = "class-index.json";
+
+// Bumped whenever the on-disk layout changes; older indexes are discarded
+const INDEX_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedArchive {
+    size: u64,
+    modified_ns: u64,
+    // Names were resolved from class headers (--accurate-class-names)
+    accurate_names: bool,
+    // (entry name, class name) for every `.class` entry
+    classes: Vec<(String, String)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    version: u32,
+    archives: HashMap<String, CachedArchive>,
+}
+
+/// The class list of each JAR scanned by a class-name search (`--cache DIR`),
+/// so later runs over an unchanged JAR skip opening it. An archive is reused
+/// only while its size and modification time are unchanged.
+#[derive(Debug)]
+pub struct ClassCache {
+    path: PathBuf,
+    archives: Mutex<HashMap<String, CachedArchive>>,
+    dirty: AtomicBool,
+}
+
+impl ClassCache {
+    /// Opens (creating it if needed) the cache in `dir`. An unreadable or
+    /// outdated index starts the cache afresh rather than failing the run.
+    pub fn open(dir: &Path) -> Result<Self, JarToolError> {
+        std::fs::create_dir_all(dir).map_err(|e| JarToolError::io(dir, e))?;
+        let path = dir.join(INDEX_FILE);
+
+        let archives = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Index>(&bytes).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .map(|index| index.archives)
+            .unwrap_or_default();
+
+        Ok(Self {
+            path,
+            archives: Mutex::new(archives),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    pub fn lookup(&self, archive: &Path, metadata: &Metadata, accurate_names: bool) -> Option<Vec<(String, String)>> {
+        let (size, modified_ns) = fingerprint(metadata)?;
+        let archives = self.archives.lock().ok()?;
+        archives
+            .get(&cache_key(archive))
+            .filter(|cached| cached.size == size && cached.modified_ns == modified_ns && cached.accurate_names == accurate_names)
+            .map(|cached| cached.classes.clone())
+    }
+
+    pub fn store(&self, archive: &Path, metadata: &Metadata, accurate_names: bool, classes: Vec<(String, String)>) {
+        let Some((size, modified_ns)) = fingerprint(metadata) else { return };
+        if let Ok(mut archives) = self.archives.lock() {
+            archives.insert(cache_key(archive), CachedArchive { size, modified_ns, accurate_names, classes });
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Writes the index back if anything changed. Archives that no longer
+    /// exist are dropped first.
+    pub fn save(&self) -> Result<(), JarToolError> {
+        if !self.dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut archives = self.archives.lock().map_err(|_| JarToolError::Poisoned("cache"))?.clone();
+        archives.retain(|path, _| Path::new(path).is_file());
+        let index = Index { version: INDEX_VERSION, archives };
+
+        // Written aside and renamed so an interrupted run never leaves half an index
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_vec(&index)?)?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn fingerprint(metadata: &Metadata) -> Option<(u64, u64)> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), modified.as_nanos() as u64))
+}
+
+// The same JAR reached through different relative paths shares one entry
+fn cache_key(archive: &Path) -> String {
+    archive
+        .canonicalize()
+        .unwrap_or_else(|_| archive.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
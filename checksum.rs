@@ -0,0 +1,113 @@
+use crate::JarToolError;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub fn parse(name: &str) -> Result<Self, JarToolError> {
+        match name.to_ascii_lowercase().as_str() {
+            "md5" => Ok(Self::Md5),
+            "sha1" => Ok(Self::Sha1),
+            "sha256" => Ok(Self::Sha256),
+            _ => Err(JarToolError::InvalidArgument(format!("unknown checksum algorithm `{}` (expected md5, sha1 or sha256)", name))),
+        }
+    }
+
+    // coreutils checksum files do not name the algorithm; the digest length does
+    fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            32 => Some(Self::Md5),
+            40 => Some(Self::Sha1),
+            64 => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+        }
+    }
+
+    /// Hex digest of a file, read in chunks so large archives are never held
+    /// in memory.
+    pub fn digest_file(self, path: &Path) -> io::Result<String> {
+        let file = File::open(path)?;
+        match self {
+            Self::Md5 => stream_digest::<Md5>(file),
+            Self::Sha1 => stream_digest::<Sha1>(file),
+            Self::Sha256 => stream_digest::<Sha256>(file),
+        }
+    }
+}
+
+fn stream_digest<D: Digest>(mut reader: impl Read) -> io::Result<String> {
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// One line of a `sha256sum`-style checksum file.
+#[derive(Debug, Clone)]
+pub struct ExpectedChecksum {
+    pub algorithm: ChecksumAlgorithm,
+    // Lowercase hex
+    pub digest: String,
+    pub path: PathBuf,
+}
+
+impl ExpectedChecksum {
+    /// Loads a checksum file in coreutils format (`<hex digest>  <path>`, with
+    /// `*` before the path in binary mode). The algorithm of each line is told
+    /// from its digest length; blank lines and `#` comments are skipped.
+    pub fn load(path: &Path) -> Result<Vec<Self>, JarToolError> {
+        let text = std::fs::read_to_string(path).map_err(|e| JarToolError::io(path, e))?;
+        let mut expected = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parsed = line.split_once(' ').and_then(|(digest, rest)| {
+                let algorithm = ChecksumAlgorithm::from_hex_len(digest.len())?;
+                if !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return None;
+                }
+                // Text mode is `digest  path`, binary mode `digest *path`
+                let file = rest.strip_prefix([' ', '*']).unwrap_or(rest);
+                (!file.is_empty()).then(|| Self {
+                    algorithm,
+                    digest: digest.to_ascii_lowercase(),
+                    path: PathBuf::from(file),
+                })
+            });
+
+            match parsed {
+                Some(entry) => expected.push(entry),
+                None => return Err(JarToolError::InvalidArgument(format!("{}:{}: expected `<md5/sha1/sha256 hex>  <path>`, got `{}`", path.display(), number + 1, line))),
+            }
+        }
+        Ok(expected)
+    }
+}
@@ -0,0 +1,799 @@
+use std::io::{self, Read};
+
+const CLASS_MAGIC: u32 = 0xCAFE_BABE;
+
+const ACC_PUBLIC: u16 = 0x0001;
+const ACC_PRIVATE: u16 = 0x0002;
+const ACC_PROTECTED: u16 = 0x0004;
+const ACC_STATIC: u16 = 0x0008;
+const ACC_FINAL: u16 = 0x0010;
+const ACC_SYNCHRONIZED: u16 = 0x0020;
+const ACC_VOLATILE: u16 = 0x0040;
+const ACC_TRANSIENT: u16 = 0x0080;
+const ACC_VARARGS: u16 = 0x0080;
+const ACC_NATIVE: u16 = 0x0100;
+const ACC_INTERFACE: u16 = 0x0200;
+const ACC_ABSTRACT: u16 = 0x0400;
+const ACC_STRICT: u16 = 0x0800;
+const ACC_ANNOTATION: u16 = 0x2000;
+
+// Flags of the Module attribute and its `requires` entries
+const ACC_OPEN: u16 = 0x0020;
+const ACC_TRANSITIVE: u16 = 0x0020;
+const ACC_STATIC_PHASE: u16 = 0x0040;
+
+#[derive(Debug, Clone)]
+pub enum Constant {
+    Utf8(String),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(u16),
+    String(u16),
+    FieldRef { class_index: u16, name_and_type_index: u16 },
+    MethodRef { class_index: u16, name_and_type_index: u16 },
+    InterfaceMethodRef { class_index: u16, name_and_type_index: u16 },
+    NameAndType { name_index: u16, descriptor_index: u16 },
+    MethodHandle { reference_kind: u8, reference_index: u16 },
+    MethodType(u16),
+    Dynamic { bootstrap_method_attr_index: u16, name_and_type_index: u16 },
+    InvokeDynamic { bootstrap_method_attr_index: u16, name_and_type_index: u16 },
+    Module(u16),
+    Package(u16),
+    // Index 0 and the second slot of Long/Double entries
+    Unusable,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeInfo {
+    pub name_index: u16,
+    pub info: Vec<u8>,
+}
+
+// Shared layout of field_info and method_info
+#[derive(Debug, Clone)]
+pub struct MemberInfo {
+    pub access_flags: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassFile {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool: Vec<Constant>,
+    pub access_flags: u16,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<MemberInfo>,
+    pub methods: Vec<MemberInfo>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+/// A `requires` directive of a module descriptor.
+#[derive(Debug, Clone)]
+pub struct ModuleRequires {
+    pub module: String,
+    pub transitive: bool,
+    pub static_phase: bool,
+}
+
+/// An `exports` (or `opens`) directive: a package in dotted form and the
+/// modules it is restricted to, empty when unqualified.
+#[derive(Debug, Clone)]
+pub struct ModuleExports {
+    pub package: String,
+    pub targets: Vec<String>,
+}
+
+/// The Module attribute of a `module-info.class`.
+#[derive(Debug, Clone)]
+pub struct ModuleDescriptor {
+    pub name: String,
+    pub open: bool,
+    pub version: Option<String>,
+    pub requires: Vec<ModuleRequires>,
+    pub exports: Vec<ModuleExports>,
+    pub opens: Vec<ModuleExports>,
+}
+
+struct ClassReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> ClassReader<R> {
+    fn u8(&mut self) -> io::Result<u8> {
+        let mut buf = [0u8; 1];
+        self.inner.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let mut buf = [0u8; 2];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0u8; 4];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    // Grows with what is actually read, so a bogus length in a truncated or
+    // hostile file cannot make it allocate gigabytes up front
+    fn bytes(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        (&mut self.inner).take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(buf)
+    }
+
+    fn attributes(&mut self) -> io::Result<Vec<AttributeInfo>> {
+        let count = self.u16()? as usize;
+        let mut attributes = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name_index = self.u16()?;
+            let len = self.u32()? as usize;
+            attributes.push(AttributeInfo { name_index, info: self.bytes(len)? });
+        }
+        Ok(attributes)
+    }
+
+    // Skips one element_value of an annotation; the values themselves (including
+    // nested annotations) are not needed to know which annotations are applied
+    fn skip_element_value(&mut self) -> io::Result<()> {
+        match self.u8()? {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' | b'c' => {
+                self.u16()?;
+            }
+            b'e' => {
+                self.u16()?;
+                self.u16()?;
+            }
+            b'@' => {
+                self.u16()?;
+                for _ in 0..self.u16()? {
+                    self.u16()?;
+                    self.skip_element_value()?;
+                }
+            }
+            b'[' => {
+                for _ in 0..self.u16()? {
+                    self.skip_element_value()?;
+                }
+            }
+            tag => return Err(invalid(format!("unknown element_value tag {}", tag))),
+        }
+        Ok(())
+    }
+
+    fn members(&mut self) -> io::Result<Vec<MemberInfo>> {
+        let count = self.u16()? as usize;
+        let mut members = Vec::with_capacity(count);
+        for _ in 0..count {
+            members.push(MemberInfo {
+                access_flags: self.u16()?,
+                name_index: self.u16()?,
+                descriptor_index: self.u16()?,
+                attributes: self.attributes()?,
+            });
+        }
+        Ok(members)
+    }
+}
+
+// Modified UTF-8 differs from UTF-8 only in writing NUL as two bytes and
+// supplementary characters as two 3-byte surrogates, so it is decoded through
+// UTF-16; malformed bytes become U+FFFD
+fn decode_modified_utf8(bytes: &[u8]) -> String {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+
+    let continuation = |b: u8| b & 0xC0 == 0x80;
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let (unit, width) = match bytes[i..] {
+            [a, ..] if a < 0x80 => (u16::from(a), 1),
+            [a, b, ..] if a & 0xE0 == 0xC0 && continuation(b) => (u16::from(a & 0x1F) << 6 | u16::from(b & 0x3F), 2),
+            [a, b, c, ..] if a & 0xF0 == 0xE0 && continuation(b) && continuation(c) => {
+                (u16::from(a & 0x0F) << 12 | u16::from(b & 0x3F) << 6 | u16::from(c & 0x3F), 3)
+            }
+            _ => (0xFFFD, 1),
+        };
+        units.push(unit);
+        i += width;
+    }
+    String::from_utf16_lossy(&units)
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl ClassFile {
+    /// Parses a complete class file: header, constant pool, members and attributes.
+    pub fn parse<R: Read>(reader: R) -> io::Result<ClassFile> {
+        let mut r = ClassReader { inner: reader };
+
+        let magic = r.u32()?;
+        if magic != CLASS_MAGIC {
+            return Err(invalid(format!("bad class file magic 0x{:08X}", magic)));
+        }
+
+        let minor_version = r.u16()?;
+        let major_version = r.u16()?;
+
+        let pool_count = r.u16()? as usize;
+        let mut constant_pool = Vec::with_capacity(pool_count);
+        constant_pool.push(Constant::Unusable);
+
+        while constant_pool.len() < pool_count {
+            let tag = r.u8()?;
+            let constant = match tag {
+                1 => {
+                    let len = r.u16()? as usize;
+                    Constant::Utf8(decode_modified_utf8(&r.bytes(len)?))
+                }
+                3 => Constant::Integer(r.u32()? as i32),
+                4 => Constant::Float(f32::from_bits(r.u32()?)),
+                5 => Constant::Long(r.u64()? as i64),
+                6 => Constant::Double(f64::from_bits(r.u64()?)),
+                7 => Constant::Class(r.u16()?),
+                8 => Constant::String(r.u16()?),
+                9 => Constant::FieldRef { class_index: r.u16()?, name_and_type_index: r.u16()? },
+                10 => Constant::MethodRef { class_index: r.u16()?, name_and_type_index: r.u16()? },
+                11 => Constant::InterfaceMethodRef { class_index: r.u16()?, name_and_type_index: r.u16()? },
+                12 => Constant::NameAndType { name_index: r.u16()?, descriptor_index: r.u16()? },
+                15 => Constant::MethodHandle { reference_kind: r.u8()?, reference_index: r.u16()? },
+                16 => Constant::MethodType(r.u16()?),
+                17 => Constant::Dynamic { bootstrap_method_attr_index: r.u16()?, name_and_type_index: r.u16()? },
+                18 => Constant::InvokeDynamic { bootstrap_method_attr_index: r.u16()?, name_and_type_index: r.u16()? },
+                19 => Constant::Module(r.u16()?),
+                20 => Constant::Package(r.u16()?),
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown constant pool tag {} at index {}", tag, constant_pool.len()
+                    )))
+                }
+            };
+
+            let wide = matches!(constant, Constant::Long(_) | Constant::Double(_));
+            constant_pool.push(constant);
+            if wide {
+                constant_pool.push(Constant::Unusable);
+            }
+        }
+
+        let access_flags = r.u16()?;
+        let this_class = r.u16()?;
+        let super_class = r.u16()?;
+
+        let interface_count = r.u16()? as usize;
+        let mut interfaces = Vec::with_capacity(interface_count);
+        for _ in 0..interface_count {
+            interfaces.push(r.u16()?);
+        }
+
+        let fields = r.members()?;
+        let methods = r.members()?;
+        let attributes = r.attributes()?;
+
+        Ok(ClassFile {
+            minor_version,
+            major_version,
+            constant_pool,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    /// Reads just the 8-byte header and returns `(major, minor)`, so version
+    /// checks never inflate more than the start of each entry.
+    pub fn read_version<R: Read>(reader: R) -> io::Result<(u16, u16)> {
+        let mut r = ClassReader { inner: reader };
+
+        let magic = r.u32()?;
+        if magic != CLASS_MAGIC {
+            return Err(invalid(format!("bad class file magic 0x{:08X}", magic)));
+        }
+
+        let minor_version = r.u16()?;
+        let major_version = r.u16()?;
+        Ok((major_version, minor_version))
+    }
+
+    pub fn utf8(&self, index: u16) -> Option<&str> {
+        match self.constant_pool.get(index as usize) {
+            Some(Constant::Utf8(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Resolves a CONSTANT_Class index to its internal name (e.g. `java/lang/String`).
+    pub fn class_name(&self, index: u16) -> Option<&str> {
+        match self.constant_pool.get(index as usize) {
+            Some(Constant::Class(name_index)) => self.utf8(*name_index),
+            _ => None,
+        }
+    }
+
+    pub fn this_class_name(&self) -> Option<&str> {
+        self.class_name(self.this_class)
+    }
+
+    pub fn member_name(&self, member: &MemberInfo) -> Option<&str> {
+        self.utf8(member.name_index)
+    }
+
+    pub fn member_descriptor(&self, member: &MemberInfo) -> Option<&str> {
+        self.utf8(member.descriptor_index)
+    }
+
+    /// Returns the Java string literals of the class: the `CONSTANT_Utf8` entries
+    /// referenced by `CONSTANT_String`, leaving out names and descriptors.
+    pub fn string_constants(&self) -> Vec<&str> {
+        self.constant_pool
+            .iter()
+            .filter_map(|constant| match constant {
+                Constant::String(index) => self.utf8(*index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Superclass internal name; `None` only for `java/lang/Object` itself.
+    pub fn super_class_name(&self) -> Option<&str> {
+        self.class_name(self.super_class)
+    }
+
+    pub fn interface_names(&self) -> Vec<&str> {
+        self.interfaces.iter().filter_map(|&index| self.class_name(index)).collect()
+    }
+
+    /// Renders the class declaration the way `javap` prints it, e.g.
+    /// `public final class com.foo.Bar extends com.foo.Base implements java.io.Serializable`.
+    pub fn declaration(&self) -> String {
+        let flags = self.access_flags;
+        let is_interface = flags & ACC_INTERFACE != 0;
+        let name = self.this_class_name().map(to_binary_name).unwrap_or_default();
+
+        let mut parts = Vec::new();
+        if flags & ACC_PUBLIC != 0 {
+            parts.push("public");
+        }
+        if flags & ACC_FINAL != 0 {
+            parts.push("final");
+        }
+        if flags & ACC_ABSTRACT != 0 && !is_interface {
+            parts.push("abstract");
+        }
+        parts.push(match (is_interface, flags & ACC_ANNOTATION != 0) {
+            (true, true) => "@interface",
+            (true, false) => "interface",
+            _ => "class",
+        });
+
+        let mut declaration = format!("{} {}", parts.join(" "), name);
+        let interfaces: Vec<String> = self.interface_names().into_iter().map(to_binary_name).collect();
+
+        // Interfaces list their superinterfaces after `extends`
+        if is_interface {
+            if !interfaces.is_empty() {
+                declaration.push_str(&format!(" extends {}", interfaces.join(", ")));
+            }
+        } else {
+            if let Some(super_name) = self.super_class_name().filter(|s| *s != "java/lang/Object") {
+                declaration.push_str(&format!(" extends {}", to_binary_name(super_name)));
+            }
+            if !interfaces.is_empty() {
+                declaration.push_str(&format!(" implements {}", interfaces.join(", ")));
+            }
+        }
+
+        declaration
+    }
+
+    /// Renders a field as Java source, e.g. `private static final java.lang.String URL;`.
+    pub fn field_declaration(&self, field: &MemberInfo) -> String {
+        let modifiers = modifiers(field.access_flags, &[
+            (ACC_STATIC, "static"),
+            (ACC_FINAL, "final"),
+            (ACC_VOLATILE, "volatile"),
+            (ACC_TRANSIENT, "transient"),
+        ]);
+        let field_type = self
+            .member_descriptor(field)
+            .and_then(|d| parse_type(d).map(|(t, _)| t))
+            .unwrap_or_else(|| "?".to_string());
+        let name = self.member_name(field).unwrap_or("?");
+
+        format!("{}{} {};", modifiers, field_type, name)
+    }
+
+    /// Renders a method signature as Java source, including a `throws` clause
+    /// from the Exceptions attribute. Constructors use the class name and the
+    /// static initializer prints as `static {};`, as in `javap`.
+    pub fn method_declaration(&self, method: &MemberInfo) -> String {
+        let name = self.member_name(method).unwrap_or("?");
+        if name == "<clinit>" {
+            return "static {};".to_string();
+        }
+
+        let modifiers = modifiers(method.access_flags, &[
+            (ACC_ABSTRACT, "abstract"),
+            (ACC_STATIC, "static"),
+            (ACC_FINAL, "final"),
+            (ACC_SYNCHRONIZED, "synchronized"),
+            (ACC_NATIVE, "native"),
+            (ACC_STRICT, "strictfp"),
+        ]);
+
+        let (mut params, return_type) = self
+            .member_descriptor(method)
+            .and_then(parse_method_descriptor)
+            .unwrap_or_else(|| (Vec::new(), "?".to_string()));
+        if method.access_flags & ACC_VARARGS != 0 {
+            if let Some(last) = params.last_mut() {
+                if let Some(element) = last.strip_suffix("[]") {
+                    *last = format!("{}...", element);
+                }
+            }
+        }
+
+        let head = if name == "<init>" {
+            self.this_class_name().map(to_binary_name).unwrap_or_default()
+        } else {
+            format!("{} {}", return_type, name)
+        };
+
+        let exceptions = self.declared_exceptions(method);
+        let throws = if exceptions.is_empty() {
+            String::new()
+        } else {
+            format!(" throws {}", exceptions.join(", "))
+        };
+
+        format!("{}{}({}){};", modifiers, head, params.join(", "), throws)
+    }
+
+    // Reads the checked exceptions from the method's Exceptions attribute
+    fn declared_exceptions(&self, method: &MemberInfo) -> Vec<String> {
+        let Some(attribute) = method.attributes.iter().find(|a| self.utf8(a.name_index) == Some("Exceptions")) else {
+            return Vec::new();
+        };
+
+        attribute
+            .info
+            .get(2..)
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .filter_map(|pair| self.class_name(u16::from_be_bytes([pair[0], pair[1]])))
+            .map(to_binary_name)
+            .collect()
+    }
+
+    /// Binary names of the annotations applied through the RuntimeVisible- and
+    /// RuntimeInvisibleAnnotations attributes in `attributes`, i.e. those of the
+    /// class itself or of one of its fields or methods.
+    pub fn annotation_types(&self, attributes: &[AttributeInfo]) -> Vec<String> {
+        let mut types = Vec::new();
+
+        for attribute in attributes {
+            if !matches!(self.utf8(attribute.name_index), Some("RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations")) {
+                continue;
+            }
+
+            // A truncated attribute still yields the annotations read before the damage
+            let mut r = ClassReader { inner: attribute.info.as_slice() };
+            let _ = self.read_annotations(&mut r, &mut types);
+        }
+
+        types
+    }
+
+    fn read_annotations(&self, r: &mut ClassReader<&[u8]>, types: &mut Vec<String>) -> io::Result<()> {
+        for _ in 0..r.u16()? {
+            let type_index = r.u16()?;
+            if let Some((name, _)) = self.utf8(type_index).and_then(parse_type) {
+                types.push(name);
+            }
+
+            for _ in 0..r.u16()? {
+                r.u16()?;
+                r.skip_element_value()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the Module attribute, present only in `module-info.class`.
+    /// `uses` and `provides` directives are not needed and are left unread.
+    pub fn module_descriptor(&self) -> Option<ModuleDescriptor> {
+        let attribute = self.attributes.iter().find(|a| self.utf8(a.name_index) == Some("Module"))?;
+        self.read_module(&mut ClassReader { inner: attribute.info.as_slice() }).ok()
+    }
+
+    fn read_module(&self, r: &mut ClassReader<&[u8]>) -> io::Result<ModuleDescriptor> {
+        let name = self.module_name(r.u16()?).ok_or_else(|| invalid("bad module name index".to_string()))?;
+        let flags = r.u16()?;
+        let version = self.utf8(r.u16()?).map(str::to_string);
+
+        let mut requires = Vec::new();
+        for _ in 0..r.u16()? {
+            let module = self.module_name(r.u16()?).unwrap_or_default();
+            let flags = r.u16()?;
+            r.u16()?; // requires_version_index
+            requires.push(ModuleRequires {
+                module,
+                transitive: flags & ACC_TRANSITIVE != 0,
+                static_phase: flags & ACC_STATIC_PHASE != 0,
+            });
+        }
+
+        let exports = self.read_module_exports(r)?;
+        let opens = self.read_module_exports(r)?;
+
+        Ok(ModuleDescriptor { name, open: flags & ACC_OPEN != 0, version, requires, exports, opens })
+    }
+
+    // `exports` and `opens` share a layout: package, flags, then the target modules
+    fn read_module_exports(&self, r: &mut ClassReader<&[u8]>) -> io::Result<Vec<ModuleExports>> {
+        let mut directives = Vec::new();
+        for _ in 0..r.u16()? {
+            let package = match self.constant_pool.get(r.u16()? as usize) {
+                Some(Constant::Package(name_index)) => self.utf8(*name_index).map(to_binary_name).unwrap_or_default(),
+                _ => String::new(),
+            };
+            r.u16()?; // flags
+            let mut targets = Vec::new();
+            for _ in 0..r.u16()? {
+                targets.extend(self.module_name(r.u16()?));
+            }
+            directives.push(ModuleExports { package, targets });
+        }
+        Ok(directives)
+    }
+
+    fn module_name(&self, index: u16) -> Option<String> {
+        match self.constant_pool.get(index as usize) {
+            Some(Constant::Module(name_index)) => self.utf8(*name_index).map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// Collects every class referenced from the constant pool together with the
+    /// kind of entry that referenced it (`class`, `fieldref`, `methodref`, ...).
+    /// Array types are unwrapped to their element class.
+    pub fn referenced_classes(&self) -> Vec<(String, &'static str)> {
+        let mut refs = Vec::new();
+
+        for (index, constant) in self.constant_pool.iter().enumerate() {
+            let (class_index, kind) = match constant {
+                Constant::Class(_) => (index as u16, "class"),
+                Constant::FieldRef { class_index, .. } => (*class_index, "fieldref"),
+                Constant::MethodRef { class_index, .. } => (*class_index, "methodref"),
+                Constant::InterfaceMethodRef { class_index, .. } => (*class_index, "interfacemethodref"),
+                _ => continue,
+            };
+
+            if let Some(name) = self.class_name(class_index) {
+                if let Some(element) = element_class_name(name) {
+                    refs.push((element.to_string(), kind));
+                }
+            }
+        }
+
+        refs
+    }
+
+    /// Every method the constant pool refers to, as (owner internal name,
+    /// method name), from both `Methodref` and `InterfaceMethodref` entries.
+    pub fn referenced_methods(&self) -> Vec<(&str, &str)> {
+        self.constant_pool
+            .iter()
+            .filter_map(|constant| match constant {
+                Constant::MethodRef { class_index, name_and_type_index }
+                | Constant::InterfaceMethodRef { class_index, name_and_type_index } => {
+                    let owner = self.class_name(*class_index)?;
+                    match self.constant_pool.get(*name_and_type_index as usize) {
+                        Some(Constant::NameAndType { name_index, .. }) => Some((owner, self.utf8(*name_index)?)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Strips array dimensions from an internal class name: `[[Lcom/Foo;` becomes
+/// `com/Foo`. Primitive arrays have no element class and yield `None`.
+pub fn element_class_name(name: &str) -> Option<&str> {
+    let stripped = name.trim_start_matches('[');
+    if stripped.len() == name.len() {
+        return Some(name);
+    }
+    stripped.strip_prefix('L').and_then(|s| s.strip_suffix(';'))
+}
+
+// Visibility first, then the member-specific modifiers in declaration order
+fn modifiers(flags: u16, specific: &[(u16, &str)]) -> String {
+    let visibility = [(ACC_PUBLIC, "public"), (ACC_PRIVATE, "private"), (ACC_PROTECTED, "protected")];
+    visibility
+        .iter()
+        .chain(specific)
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| format!("{} ", name))
+        .collect()
+}
+
+/// Parses one field type off the front of a descriptor, returning its Java
+/// spelling (`[Ljava/lang/String;` becomes `java.lang.String[]`) and the rest.
+pub fn parse_type(descriptor: &str) -> Option<(String, &str)> {
+    let mut chars = descriptor.chars();
+    let java_type = match chars.next()? {
+        'B' => "byte".to_string(),
+        'C' => "char".to_string(),
+        'D' => "double".to_string(),
+        'F' => "float".to_string(),
+        'I' => "int".to_string(),
+        'J' => "long".to_string(),
+        'S' => "short".to_string(),
+        'Z' => "boolean".to_string(),
+        'V' => "void".to_string(),
+        'L' => {
+            let end = descriptor.find(';')?;
+            return Some((to_binary_name(&descriptor[1..end]), &descriptor[end + 1..]));
+        }
+        '[' => {
+            let (element, rest) = parse_type(&descriptor[1..])?;
+            return Some((format!("{}[]", element), rest));
+        }
+        _ => return None,
+    };
+    Some((java_type, chars.as_str()))
+}
+
+/// Splits a method descriptor such as `(Ljava/lang/String;I)V` into Java
+/// parameter types and the return type.
+pub fn parse_method_descriptor(descriptor: &str) -> Option<(Vec<String>, String)> {
+    let mut rest = descriptor.strip_prefix('(')?;
+    let mut params = Vec::new();
+
+    while !rest.starts_with(')') {
+        let (param, remaining) = parse_type(rest)?;
+        params.push(param);
+        rest = remaining;
+    }
+
+    let (return_type, _) = parse_type(&rest[1..])?;
+    Some((params, return_type))
+}
+
+/// Maps a class file major version to the Java release that introduced it
+/// (45 is 1.1, 52 is 8, 61 is 17).
+pub fn java_release(major_version: u16) -> u16 {
+    major_version.saturating_sub(44)
+}
+
+/// Human-readable release name: `Java 1.4` below 49, `Java 8` from there on.
+pub fn java_release_name(major_version: u16) -> String {
+    match java_release(major_version) {
+        release @ 1..=4 => format!("Java 1.{}", release),
+        release => format!("Java {}", release),
+    }
+}
+
+/// Converts a dotted Java name to the slash-separated internal form.
+pub fn to_internal_name(name: &str) -> String {
+    name.replace('.', "/")
+}
+
+/// Converts an internal class name back to the dotted form.
+pub fn to_binary_name(name: &str) -> String {
+    name.replace('/', ".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A minimal `public class com.app.Main` with a string literal, a long, a
+    // double and a Modified UTF-8 constant holding NUL and U+1F600
+    fn minimal_class() -> Vec<u8> {
+        let mut class = Vec::new();
+        class.extend(CLASS_MAGIC.to_be_bytes());
+        class.extend([0, 0, 0, 52]);
+        class.extend(12u16.to_be_bytes());
+
+        let utf8 = |class: &mut Vec<u8>, bytes: &[u8]| {
+            class.push(1);
+            class.extend((bytes.len() as u16).to_be_bytes());
+            class.extend(bytes);
+        };
+        utf8(&mut class, b"com/app/Main"); // #1
+        class.extend([7, 0, 1]); // #2 Class
+        utf8(&mut class, b"java/lang/Object"); // #3
+        class.extend([7, 0, 3]); // #4 Class
+        class.push(5); // #5 Long, #6 unusable
+        class.extend(42i64.to_be_bytes());
+        class.extend([8, 0, 8]); // #7 String
+        utf8(&mut class, b"hello"); // #8
+        class.push(6); // #9 Double, #10 unusable
+        class.extend(1.5f64.to_bits().to_be_bytes());
+        utf8(&mut class, &[b'a', 0xC0, 0x80, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]); // #11
+
+        class.extend(ACC_PUBLIC.to_be_bytes());
+        class.extend([0, 2, 0, 4]); // this, super
+        class.extend([0, 0, 0, 0, 0, 0, 0, 0]); // no interfaces, fields, methods or attributes
+        class
+    }
+
+    #[test]
+    fn parses_a_minimal_class() {
+        let class = ClassFile::parse(&minimal_class()[..]).unwrap();
+
+        assert_eq!(class.major_version, 52);
+        assert_eq!(class.this_class_name(), Some("com/app/Main"));
+        assert_eq!(class.super_class_name(), Some("java/lang/Object"));
+        assert_eq!(class.string_constants(), vec!["hello"]);
+        assert_eq!(class.declaration(), "public class com.app.Main");
+    }
+
+    #[test]
+    fn long_and_double_take_two_pool_slots() {
+        let class = ClassFile::parse(&minimal_class()[..]).unwrap();
+
+        assert_eq!(class.constant_pool.len(), 12);
+        assert!(matches!(class.constant_pool[5], Constant::Long(42)));
+        assert!(matches!(class.constant_pool[6], Constant::Unusable));
+        assert!(matches!(class.constant_pool[9], Constant::Double(d) if d == 1.5));
+        assert!(matches!(class.constant_pool[10], Constant::Unusable));
+        assert_eq!(class.utf8(8), Some("hello"));
+    }
+
+    #[test]
+    fn decodes_modified_utf8() {
+        let class = ClassFile::parse(&minimal_class()[..]).unwrap();
+        assert_eq!(class.utf8(11), Some("a\0\u{1F600}"));
+        assert_eq!(decode_modified_utf8(&[b'x', 0xFF, b'y']), "x\u{FFFD}y");
+    }
+
+    #[test]
+    fn truncated_class_is_an_error() {
+        let class = minimal_class();
+        for len in 0..class.len() {
+            assert!(ClassFile::parse(&class[..len]).is_err(), "parsed a class cut at {} bytes", len);
+        }
+        assert!(ClassFile::read_version(&class[..6]).is_err());
+    }
+
+    #[test]
+    fn oversized_attribute_length_is_an_error() {
+        let mut class = minimal_class();
+        class.truncate(class.len() - 2);
+        // One attribute claiming 4 GiB of data that is not there
+        class.extend([0, 1, 0, 1, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3]);
+        assert!(ClassFile::parse(&class[..]).is_err());
+    }
+}
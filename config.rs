@@ -0,0 +1,48 @@
+use crate::JarToolError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File picked up from the working directory when `--config` is not given.
+pub const DEFAULT_CONFIG_FILE: &str = "jartool.toml";
+
+/// Defaults from a `jartool.toml` scan profile. Every field is optional; a
+/// flag given on the command line always wins over the file.
+///
+/// ```toml
+/// exclude = ["target", ".git"]
+/// jobs = 8
+/// min-size = 1024
+/// color = "never"
+///
+/// [type-map]
+/// tf = "terraform"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub exclude: Vec<String>,
+    // 0 means one per CPU core, as on the command line
+    pub jobs: Option<usize>,
+    pub min_size: Option<u64>,
+    pub color: Option<String>,
+    pub type_map: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, JarToolError> {
+        let text = std::fs::read_to_string(path).map_err(|e| JarToolError::io(path, e))?;
+        let config = toml::from_str(&text).map_err(|e| JarToolError::InvalidArgument(format!("{}: {}", path.display(), e)))?;
+        Ok(config)
+    }
+
+    /// Loads `explicit` (which must exist), else `./jartool.toml` if there is
+    /// one, else the built-in defaults.
+    pub fn discover(explicit: Option<&Path>) -> Result<Self, JarToolError> {
+        match explicit {
+            Some(path) => Self::load(path),
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => Self::load(Path::new(DEFAULT_CONFIG_FILE)),
+            None => Ok(Self::default()),
+        }
+    }
+}
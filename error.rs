@@ -0,0 +1,57 @@
+use std::io;
+use std::path::PathBuf;
+
+/// Everything the library API can fail with, so callers can tell a missing
+/// file from a bad pattern from a corrupt archive.
+#[derive(Debug, thiserror::Error)]
+pub enum JarToolError {
+    /// Reading or writing failed; `path` names the file when one is involved.
+    #[error("{}{source}", path.as_ref().map(|p| format!("{}: ", p.display())).unwrap_or_default())]
+    Io {
+        path: Option<PathBuf>,
+        #[source]
+        source: io::Error,
+    },
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
+    #[error(transparent)]
+    Glob(#[from] globset::Error),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+    /// Encoding a JSON export or the class cache failed.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    /// A remote archive (`--url`) could not be downloaded.
+    #[error("{url}: {message}")]
+    Download { url: String, message: String },
+    /// A bad option value, or a malformed input file (patterns, queries,
+    /// checksums, advisories, configuration).
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// A worker panicked while holding the named shared state.
+    #[error("{0} lock poisoned")]
+    Poisoned(&'static str),
+    #[error("cannot start {jobs} worker threads: {source}")]
+    ThreadPool {
+        jobs: usize,
+        #[source]
+        source: rayon::ThreadPoolBuildError,
+    },
+}
+
+impl JarToolError {
+    /// An I/O error on `path`, reported as `<path>: <error>`.
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        JarToolError::Io { path: Some(path.into()), source }
+    }
+}
+
+impl From<io::Error> for JarToolError {
+    fn from(source: io::Error) -> Self {
+        JarToolError::Io { path: None, source }
+    }
+}
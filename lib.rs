@@ -0,0 +1,5829 @@
+//! Search engine behind the `jartool` CLI: JAR/ZIP/class/text scanning with
+//! results returned as `SearchResult`s.
+
+pub mod buildinfo;
+pub mod cache;
+pub mod checksum;
+pub mod classfile;
+pub mod config;
+pub mod error;
+pub mod manifest;
+pub mod maven;
+pub mod properties;
+pub mod reflection;
+pub mod remote;
+pub mod report;
+pub mod signing;
+
+use buildinfo::BuildInfo;
+use cache::ClassCache;
+use checksum::{ChecksumAlgorithm, ExpectedChecksum};
+use classfile::ClassFile;
+pub use error::JarToolError;
+use manifest::Manifest;
+use maven::{Advisory, MavenCoordinate};
+use reflection::ReflectionSymbol;
+use signing::SignatureStatus;
+use colored::*;
+use csv::Writer;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use flate2::read::GzDecoder;
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use indicatif::{ProgressBar, ProgressStyle};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use serde::{Serialize, Serializer};
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use walkdir::WalkDir;
+use zip::read::ZipFile;
+use zip::ZipArchive;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchResult {
+    // Display form: `archive!entry` for archive members, else the file path
+    pub file_location: String,
+    // The on-disk archive holding the match, `None` for plain files
+    pub archive_path: Option<String>,
+    // Path inside `archive_path` (nested levels joined with `!`), or the file path
+    pub entry_path: String,
+    pub line_number: Option<usize>,
+    pub line_content: String,
+    pub match_type: String,
+    // Originating query when several searches run in one pass (--queries)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    // Surrounding lines (--context, --before-context, --after-context),
+    // including the matching line itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<Vec<String>>,
+    // Line number of the first context line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_start: Option<usize>,
+    // Start of the matched string run within the entry, for binary and bytecode hits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub byte_offset: Option<usize>,
+    // Byte ranges of `line_content` that matched, highlighted when printing
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub match_spans: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchStats {
+    pub total_jars: usize,
+    pub total_zip_files: usize,
+    pub total_tar_files: usize,
+    pub total_class_files: usize,
+    pub total_java_files: usize,
+    pub total_other_files: usize,
+    pub matches_found: usize,
+    pub files_processed: usize,
+    // Uncompressed bytes run through the matchers
+    pub bytes_scanned: u64,
+    #[serde(rename = "elapsed_ms", serialize_with = "serialize_duration_ms")]
+    pub elapsed_time: Duration,
+    // Time spent in each phase of a master search, in the order they ran
+    #[serde(rename = "phase_ms", serialize_with = "serialize_phase_ms", skip_serializing_if = "Vec::is_empty")]
+    pub phase_times: Vec<(String, Duration)>,
+}
+
+impl SearchStats {
+    pub fn files_per_sec(&self) -> f64 {
+        per_second(self.files_processed, self.elapsed_time)
+    }
+
+    pub fn classes_per_sec(&self) -> f64 {
+        per_second(self.total_class_files, self.elapsed_time)
+    }
+
+    pub fn mb_per_sec(&self) -> f64 {
+        per_second(self.bytes_scanned as usize, self.elapsed_time) / (1024.0 * 1024.0)
+    }
+}
+
+fn per_second(count: usize, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        0.0
+    } else {
+        count as f64 / elapsed.as_secs_f64()
+    }
+}
+
+fn serialize_duration_ms<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+fn serialize_phase_ms<S: Serializer>(phases: &[(String, Duration)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(phases.iter().map(|(phase, duration)| (phase, duration.as_millis() as u64)))
+}
+
+// The stats plus the derived throughput figures, as written to JSON
+#[derive(Serialize)]
+struct StatsReport<'a> {
+    #[serde(flatten)]
+    stats: &'a SearchStats,
+    files_per_sec: f64,
+    classes_per_sec: f64,
+    mb_per_sec: f64,
+    parallel_jobs: usize,
+}
+
+impl<'a> StatsReport<'a> {
+    fn new(stats: &'a SearchStats, parallel_jobs: usize) -> Self {
+        Self {
+            stats,
+            files_per_sec: stats.files_per_sec(),
+            classes_per_sec: stats.classes_per_sec(),
+            mb_per_sec: stats.mb_per_sec(),
+            parallel_jobs,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    results: &'a [SearchResult],
+    stats: StatsReport<'a>,
+    failures: Vec<FailedFile>,
+}
+
+#[derive(Serialize)]
+struct FailedFile {
+    path: String,
+    error: String,
+}
+
+// "-" writes to stdout so machine-readable output can be piped
+fn create_output(filename: &str) -> std::io::Result<Box<dyn Write + Send>> {
+    if filename == "-" {
+        return Ok(Box::new(std::io::stdout()));
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(filename)?;
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+#[derive(Debug)]
+pub enum QueryKind {
+    Class(String),
+    ClassContains(String),
+    Package(String),
+    Content(Regex),
+    EntryName(GlobMatcher),
+}
+
+#[derive(Debug)]
+pub struct BatchQuery {
+    pub label: String,
+    pub kind: QueryKind,
+}
+
+impl BatchQuery {
+    /// Builds a query of the given type (`class`, `class-contains`, `package`,
+    /// `content`, `entry-name`); `ignore_case` applies to content regexes.
+    pub fn new(query_type: &str, pattern: &str, ignore_case: bool) -> Result<Self, JarToolError> {
+        let kind = match query_type {
+            "class" => QueryKind::Class(pattern.to_string()),
+            "class-contains" => QueryKind::ClassContains(pattern.to_string()),
+            "package" => QueryKind::Package(pattern.replace('.', "/")),
+            "content" if ignore_case => QueryKind::Content(Regex::new(&format!("(?i){}", pattern))?),
+            "content" => QueryKind::Content(Regex::new(pattern)?),
+            "entry-name" => QueryKind::EntryName(Glob::new(pattern)?.compile_matcher()),
+            other => return Err(JarToolError::InvalidArgument(format!("unknown query type `{}`", other))),
+        };
+
+        Ok(Self {
+            label: format!("{} {}", query_type, pattern),
+            kind,
+        })
+    }
+
+    // Parses one `type pattern` line from a --queries file
+    pub fn parse(line: &str) -> Result<Self, JarToolError> {
+        let (query_type, pattern) = line
+            .split_once(char::is_whitespace)
+            .map(|(t, p)| (t, p.trim()))
+            .ok_or_else(|| JarToolError::InvalidArgument(format!("expected `type pattern`, got `{}`", line)))?;
+
+        Self::new(query_type, pattern, false)
+    }
+
+    pub fn load(path: &Path) -> Result<Vec<Self>, JarToolError> {
+        read_query_lines(path, "queries", Self::parse)
+    }
+
+    /// Loads a --pattern-file: one pattern per line, all of the same type.
+    /// Results are labelled with the bare pattern.
+    pub fn load_patterns(path: &Path, query_type: &str, ignore_case: bool) -> Result<Vec<Self>, JarToolError> {
+        read_query_lines(path, "patterns", |line| {
+            let mut query = Self::new(query_type, line, ignore_case)?;
+            query.label = line.to_string();
+            Ok(query)
+        })
+    }
+}
+
+/// Narrows `--module-info` to modules with one particular directive.
+#[derive(Debug, Clone)]
+pub enum ModuleFilter {
+    Requires(String),
+    Exports(String),
+}
+
+impl ModuleFilter {
+    /// Parses `requires:<module>` or `exports:<package>`.
+    pub fn parse(text: &str) -> Result<Self, JarToolError> {
+        match text.split_once(':') {
+            Some(("requires", module)) if !module.is_empty() => Ok(Self::Requires(module.to_string())),
+            Some(("exports", package)) if !package.is_empty() => Ok(Self::Exports(package.to_string())),
+            _ => Err(JarToolError::InvalidArgument(format!("expected `requires:<module>` or `exports:<package>`, got `{}`", text))),
+        }
+    }
+}
+
+/// Reads a --pattern-file: one pattern per line, skipping blank lines and `#` comments.
+pub fn read_pattern_file(path: &Path) -> Result<Vec<String>, JarToolError> {
+    read_query_lines(path, "patterns", |line| Ok(line.to_string()))
+}
+
+/// Reads a newline-separated file list (e.g. piped from `find`) for --stdin.
+/// Paths that do not exist are reported on stderr and skipped.
+pub fn read_file_list<R: BufRead>(reader: R) -> Result<Vec<PathBuf>, JarToolError> {
+    let mut files = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let path = PathBuf::from(line);
+        if path.exists() {
+            files.push(path);
+        } else {
+            eprintln!("{} Skipping missing path: {}", "WARNING".yellow(), line);
+        }
+    }
+
+    Ok(files)
+}
+
+// Parses every non-blank, non-`#` line, prefixing errors with file:line;
+// `what` names the lines in the error for a file that has none
+fn read_query_lines<T, F>(path: &Path, what: &str, parse: F) -> Result<Vec<T>, JarToolError>
+where
+    F: Fn(&str) -> Result<T, JarToolError>,
+{
+    let reader = BufReader::new(File::open(path)?);
+    let mut queries = Vec::new();
+
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let query = parse(line)
+            .map_err(|e| JarToolError::InvalidArgument(format!("{}:{}: {}", path.display(), line_num + 1, e)))?;
+        queries.push(query);
+    }
+
+    if queries.is_empty() {
+        return Err(JarToolError::InvalidArgument(format!("no {} found in {}", what, path.display())));
+    }
+    Ok(queries)
+}
+
+// The content patterns of one search. A RegexSet answers "does anything match"
+// in a single scan of each line, so extra patterns cost little; the individual
+// regexes are only run on matching lines, to locate the spans.
+#[derive(Debug)]
+struct PatternSet {
+    set: RegexSet,
+    regexes: Vec<Regex>,
+    labels: Vec<String>,
+    // Whether results name the pattern(s) they matched
+    tagged: bool,
+}
+
+impl PatternSet {
+    fn new(patterns: &[String], ignore_case: bool) -> Result<Self, regex::Error> {
+        let set = RegexSetBuilder::new(patterns).case_insensitive(ignore_case).build()?;
+        let regexes = patterns
+            .iter()
+            .map(|p| RegexBuilder::new(p).case_insensitive(ignore_case).build())
+            .collect::<Result<_, _>>()?;
+        Ok(Self { set, regexes, labels: patterns.to_vec(), tagged: patterns.len() > 1 })
+    }
+
+    // Tail of a cut binary run carried into the next piece; a literal pattern
+    // can then never be split across two pieces
+    fn overlap(&self) -> usize {
+        self.labels.iter().map(|p| p.len().saturating_sub(1)).max().unwrap_or(0)
+    }
+
+    // Where the matching patterns hit, sorted, with overlaps between patterns merged
+    fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = self
+            .set
+            .matches(text)
+            .into_iter()
+            .flat_map(|i| self.regexes[i].find_iter(text))
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        spans.sort_unstable();
+        merge_spans(spans)
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        self.set.is_match(text)
+    }
+
+    // Results are only tagged when several patterns are in play (or, for
+    // batch queries, always)
+    fn query_for(&self, text: &str) -> Option<String> {
+        if !self.tagged {
+            return None;
+        }
+        let matched: Vec<&str> = self.set.matches(text).into_iter().map(|i| self.labels[i].as_str()).collect();
+        // Lines selected by --invert-match matched nothing to tag them with
+        (!matched.is_empty()).then(|| matched.join(", "))
+    }
+}
+
+// One JAR's copy of a class or resource
+#[derive(Debug)]
+struct EntryCopy {
+    jar_path: PathBuf,
+    entry_name: String,
+    digest: [u8; 32],
+}
+
+// One class's direct supertypes, for --extends/--implements
+#[derive(Debug, Clone)]
+struct ClassNode {
+    name: String,
+    location: Location,
+    super_name: Option<String>,
+    interfaces: Vec<String>,
+}
+
+// One class's constant-pool class references, for --references --transitive
+#[derive(Debug, Clone)]
+struct ClassRefs {
+    // Internal name (`com/foo/Bar`)
+    name: String,
+    location: Location,
+    refs: Vec<(String, &'static str)>,
+}
+
+// Fully-qualified class name (or resource path) -> every JAR entry that ships it
+type DigestIndex = Arc<Mutex<HashMap<String, Vec<EntryCopy>>>>;
+
+// Package name -> the JARs with classes in it
+type PackageIndex = Arc<Mutex<HashMap<String, HashSet<PathBuf>>>>;
+
+// (file location, query) pairs already reported in mini mode
+type MiniIndex = Arc<Mutex<HashSet<(String, Option<String>)>>>;
+
+// One JAR's packages for --shaded: class counts per (relocated root, original
+// root), and the packages that carry no relocation prefix
+#[derive(Debug, Default)]
+struct ShadedJar {
+    jar_path: PathBuf,
+    relocated: BTreeMap<(String, String), usize>,
+    plain_packages: HashSet<String>,
+}
+
+fn merge_spans(spans: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// Spans that survive cutting their line at byte `cut`: ones crossing the cut
+// end there, ones starting past it are dropped
+fn clip_spans(spans: &[(usize, usize)], cut: usize) -> Vec<(usize, usize)> {
+    spans.iter().filter(|&&(start, _)| start < cut).map(|&(start, end)| (start, end.min(cut))).collect()
+}
+
+// Colours the matched spans of a result line, leaving the rest plain
+fn highlight(text: &str, spans: &[(usize, usize)]) -> String {
+    let mut highlighted = String::with_capacity(text.len());
+    let mut position = 0;
+    for &(start, end) in spans {
+        // Spans come from the same text, but guard against ones that do not fit
+        let (Some(before), Some(matched)) = (text.get(position..start), text.get(start..end)) else {
+            continue;
+        };
+        if !before.is_empty() {
+            highlighted.push_str(&before.white().to_string());
+        }
+        highlighted.push_str(&matched.red().bold().to_string());
+        position = end;
+    }
+    if position < text.len() {
+        highlighted.push_str(&text[position..].white().to_string());
+    }
+    highlighted
+}
+
+// Path, then line, then offset. Each file is scanned by one worker, so results
+// tied on all three keep the order that worker found them in.
+fn by_location(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    a.file_location
+        .cmp(&b.file_location)
+        .then(a.line_number.cmp(&b.line_number))
+        .then(a.byte_offset.cmp(&b.byte_offset))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+const BINARY_CHUNK_SIZE: usize = 64 * 1024;
+// Longer printable runs are handed out in pieces instead of growing one string
+const MAX_BINARY_STRING_LEN: usize = BINARY_CHUNK_SIZE;
+pub const DEFAULT_MIN_STRING_LEN: usize = 4;
+
+/// Ordinary JARs compress 2-10x; bombs reach hundreds or thousands.
+pub const DEFAULT_BOMB_RATIO: f64 = 100.0;
+
+/// Longest `line_content` shown or exported before it is cut short.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 500;
+
+// Appended to a file's name for the copy --replace writes before renaming it over the original
+const REPLACE_TEMP_SUFFIX: &str = ".jartool.tmp";
+
+// Where ServiceLoader looks for provider-configuration files
+const SERVICES_DIR: &str = "META-INF/services/";
+
+// File names (any extension, any directory) that --licenses collects
+const LICENSE_FILE_PREFIXES: &[&str] = &["LICENSE", "LICENCE", "NOTICE", "COPYING"];
+
+// How far into a license file to look for an SPDX tag or a known title
+const LICENSE_SCAN_LINES: usize = 30;
+
+// Titles of common licenses, matched against the lowercased, whitespace-collapsed
+// head of the file; more specific titles come first
+const KNOWN_LICENSES: &[(&str, &str)] = &[
+    ("apache license version 2.0", "Apache-2.0"),
+    ("apache license, version 2.0", "Apache-2.0"),
+    ("eclipse public license - v 2.0", "EPL-2.0"),
+    ("eclipse public license - v 1.0", "EPL-1.0"),
+    ("gnu lesser general public license version 2.1", "LGPL-2.1"),
+    ("gnu lesser general public license version 3", "LGPL-3.0"),
+    ("gnu general public license version 2", "GPL-2.0"),
+    ("gnu general public license version 3", "GPL-3.0"),
+    ("common development and distribution license (cddl) version 1.0", "CDDL-1.0"),
+    ("mozilla public license version 2.0", "MPL-2.0"),
+    ("mit license", "MIT"),
+    ("permission is hereby granted, free of charge", "MIT"),
+];
+
+fn is_license_entry(entry_name: &str) -> bool {
+    let file_name = entry_name.rsplit('/').next().unwrap_or_default().to_uppercase();
+    !file_name.ends_with(".CLASS") && LICENSE_FILE_PREFIXES.iter().any(|prefix| file_name.starts_with(prefix))
+}
+
+// Entries nearly every JAR carries its own copy of, which are no conflict
+fn is_per_jar_metadata(entry_name: &str) -> bool {
+    let upper = entry_name.to_uppercase();
+    entry_name == manifest::MANIFEST_PATH
+        || entry_name == "META-INF/INDEX.LIST"
+        || entry_name.starts_with("META-INF/maven/")
+        || entry_name.starts_with(SERVICES_DIR)
+        || (upper.starts_with("META-INF/") && [".SF", ".RSA", ".DSA", ".EC"].iter().any(|ext| upper.ends_with(ext)))
+        || is_license_entry(entry_name)
+}
+
+// An explicit `SPDX-License-Identifier:` tag wins; otherwise a known title
+fn identify_license(text: &str) -> Option<String> {
+    let head: Vec<&str> = text.lines().take(LICENSE_SCAN_LINES).collect();
+
+    for line in &head {
+        if let Some((_, tag)) = line.split_once("SPDX-License-Identifier:") {
+            let id = tag.trim().trim_end_matches("*/").trim_end_matches("-->").trim();
+            if !id.is_empty() {
+                return Some(id.to_string());
+            }
+        }
+    }
+
+    let normalized = head.join(" ").split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    KNOWN_LICENSES
+        .iter()
+        .find(|(title, _)| normalized.contains(title))
+        .map(|(_, id)| id.to_string())
+}
+
+/// Package segments that shading plugins commonly insert when relocating a
+/// dependency (`shaded.com.google.common`); `--shade-prefix` adds more.
+pub const DEFAULT_SHADE_PREFIXES: &[&str] = &["shaded", "shadow", "repackaged"];
+
+// Where a relocation prefix (split into segments) first occurs in `package`:
+// the relocated root, running through the prefix and the original library's
+// first two segments, and that original root
+fn relocation_root(package: &str, prefixes: &[Vec<String>]) -> Option<(String, String)> {
+    let segments: Vec<&str> = package.split('.').collect();
+    for start in 0..segments.len() {
+        for prefix in prefixes {
+            let end = start + prefix.len();
+            // A package that merely ends in `shaded` relocates nothing
+            if end < segments.len() && segments[start..end].iter().zip(prefix).all(|(s, p)| s.eq_ignore_ascii_case(p)) {
+                let original_end = (end + 2).min(segments.len());
+                return Some((segments[..original_end].join("."), segments[end..original_end].join(".")));
+            }
+        }
+    }
+    None
+}
+
+// The name the module system gives a JAR without module-info.class on the
+// module path, following ModuleFinder: the file name minus `.jar` and any
+// version (`-` then a digit), with every non-alphanumeric run turned into one dot
+fn automatic_module_name(file_name: &str) -> String {
+    let stem = file_name.strip_suffix(".jar").unwrap_or(file_name);
+
+    let bytes = stem.as_bytes();
+    let version_start = (0..bytes.len()).find(|&i| {
+        if bytes[i] != b'-' {
+            return false;
+        }
+        let digits = bytes[i + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+        digits > 0 && matches!(bytes.get(i + 1 + digits), None | Some(b'.'))
+    });
+    let stem = &stem[..version_start.unwrap_or(stem.len())];
+
+    stem.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+// Streams printable runs out of binary data (similar to the strings command).
+// Input is consumed in fixed-size chunks so memory stays bounded regardless of
+// entry size; a run spanning a chunk boundary is carried over intact. Runs
+// shorter than min_len are dropped. Returns the number of bytes read.
+// `overlap` bytes of a run cut at MAX_BINARY_STRING_LEN start the next piece,
+// so a match no longer than `overlap + 1` that straddles the cut is still seen whole
+fn for_each_binary_string<R: Read, F: FnMut(usize, &str)>(mut reader: R, min_len: usize, overlap: usize, mut f: F) -> std::io::Result<usize> {
+    let mut chunk = vec![0u8; BINARY_CHUNK_SIZE];
+    let mut current_string = String::new();
+    let overlap = overlap.min(MAX_BINARY_STRING_LEN / 2);
+    // Offset of the next byte to read, and of the start of the current run
+    let mut offset = 0;
+    let mut run_start = 0;
+
+    loop {
+        let bytes_read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        for &byte in &chunk[..bytes_read] {
+            if byte.is_ascii_graphic() || byte == b' ' || byte == b'\t' {
+                if current_string.is_empty() {
+                    run_start = offset;
+                }
+                current_string.push(byte as char);
+                if current_string.len() >= MAX_BINARY_STRING_LEN {
+                    if current_string.len() >= min_len {
+                        f(run_start, &current_string);
+                    }
+                    current_string.drain(..current_string.len() - overlap);
+                    run_start = offset + 1 - overlap;
+                }
+            } else {
+                if current_string.len() >= min_len {
+                    f(run_start, &current_string);
+                }
+                current_string.clear();
+            }
+            offset += 1;
+        }
+    }
+
+    // Check final string
+    if current_string.len() >= min_len {
+        f(run_start, &current_string);
+    }
+    Ok(offset)
+}
+
+// Buffers the lines around text matches for --context so streaming readers
+// never need to re-read the input
+struct ContextTracker {
+    before: usize,
+    after: usize,
+    history: VecDeque<String>,
+    pending: VecDeque<(SearchResult, usize)>,
+}
+
+impl ContextTracker {
+    fn new(before: usize, after: usize) -> Self {
+        Self {
+            before,
+            after,
+            history: VecDeque::with_capacity(before),
+            pending: VecDeque::new(),
+        }
+    }
+
+    // Feeds one line (and its result, if it matched); results are handed to
+    // `emit` once their trailing context is complete
+    fn push(&mut self, line: &str, line_number: usize, result: Option<SearchResult>, emit: &mut dyn FnMut(SearchResult)) {
+        if self.before == 0 && self.after == 0 {
+            if let Some(result) = result {
+                emit(result);
+            }
+            return;
+        }
+
+        for (pending, remaining) in self.pending.iter_mut() {
+            if let Some(context) = pending.context.as_mut() {
+                context.push(line.to_string());
+            }
+            *remaining -= 1;
+        }
+        while self.pending.front().is_some_and(|(_, remaining)| *remaining == 0) {
+            if let Some((done, _)) = self.pending.pop_front() {
+                emit(done);
+            }
+        }
+
+        if let Some(mut result) = result {
+            let mut context: Vec<String> = self.history.iter().cloned().collect();
+            context.push(line.to_string());
+            result.context_start = Some(line_number - self.history.len());
+            result.context = Some(context);
+            if self.after == 0 {
+                emit(result);
+            } else {
+                self.pending.push_back((result, self.after));
+            }
+        }
+
+        self.history.push_back(line.to_string());
+        if self.history.len() > self.before {
+            self.history.pop_front();
+        }
+    }
+
+    // Emits matches near the end of input with whatever trailing context exists
+    fn finish(&mut self, emit: &mut dyn FnMut(SearchResult)) {
+        while let Some((result, _)) = self.pending.pop_front() {
+            emit(result);
+        }
+    }
+}
+
+// Line number of the last context line captured with a result
+fn last_context_line(result: &SearchResult) -> usize {
+    match (&result.context, result.context_start) {
+        (Some(context), Some(start)) => start + context.len().saturating_sub(1),
+        _ => result.line_number.unwrap_or(0),
+    }
+}
+
+// A result's context lines with their numbers, minus those up to `shown_through`
+fn unshown_context(context: &[String], start: usize, shown_through: usize) -> impl Iterator<Item = (usize, &String)> {
+    context.iter().enumerate().map(move |(offset, line)| (start + offset, line)).filter(move |(number, _)| *number > shown_through)
+}
+
+fn is_nested_archive(file_name: &str) -> bool {
+    let lower = file_name.to_lowercase();
+    lower.ends_with(".jar") || lower.ends_with(".war") || lower.ends_with(".ear")
+}
+
+fn is_tar_archive(file_name: &str) -> bool {
+    let lower = file_name.to_lowercase();
+    lower.ends_with(".tar") || lower.ends_with(".tgz") || lower.ends_with(".tar.gz")
+}
+
+// Per-file time budget for --timeout-per-file, shared by every reader (and
+// clone of it) working on the same file
+#[derive(Debug)]
+struct ReadBudget {
+    deadline: Instant,
+    location: String,
+    expired: AtomicBool,
+}
+
+impl ReadBudget {
+    fn check(&self) -> std::io::Result<()> {
+        if Instant::now() < self.deadline {
+            return Ok(());
+        }
+        if !self.expired.swap(true, Ordering::Relaxed) {
+            eprintln!("{} Timed out, skipping the rest of {}", "WARNING".yellow(), self.location);
+        }
+        Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "per-file timeout exceeded"))
+    }
+}
+
+// Fails every read once the file's budget is spent. Decompression pulls its
+// input through here in small chunks, so a zip bomb or a pathological archive
+// is abandoned mid-entry instead of hanging the scan.
+#[derive(Debug, Clone)]
+struct TimedReader<R> {
+    inner: R,
+    budget: Option<Arc<ReadBudget>>,
+}
+
+impl<R: Read> Read for TimedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(budget) = &self.budget {
+            budget.check()?;
+        }
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for TimedReader<R> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+// Backing store for an opened archive: the file itself, or a read-only
+// mapping of it (--mmap) that turns entry seeks into plain memory accesses.
+// Clones are cheap and independent, so parallel workers can each seek freely.
+#[derive(Debug, Clone)]
+enum ArchiveSource {
+    File(ArchiveFile),
+    Mapped(Cursor<SharedMmap>),
+}
+
+// An archive read through its own file handle. A clone does not share the
+// handle (and with it the file position): it opens the file again on first use.
+#[derive(Debug)]
+struct ArchiveFile {
+    path: Arc<Path>,
+    file: Option<File>,
+    pos: u64,
+}
+
+impl ArchiveFile {
+    fn new(path: &Path, file: File) -> Self {
+        Self { path: Arc::from(path), file: Some(file), pos: 0 }
+    }
+
+    fn handle(&mut self) -> std::io::Result<&mut File> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => {
+                let mut file = File::open(&self.path)?;
+                file.seek(std::io::SeekFrom::Start(self.pos))?;
+                file
+            }
+        };
+        Ok(self.file.insert(file))
+    }
+}
+
+impl Clone for ArchiveFile {
+    fn clone(&self) -> Self {
+        Self { path: Arc::clone(&self.path), file: None, pos: self.pos }
+    }
+}
+
+impl Read for ArchiveFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.handle()?.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for ArchiveFile {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.pos = self.handle()?.seek(pos)?;
+        Ok(self.pos)
+    }
+}
+
+// One mapping shared by every clone of an archive
+#[derive(Debug, Clone)]
+struct SharedMmap(Arc<Mmap>);
+
+impl AsRef<[u8]> for SharedMmap {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Read for ArchiveSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            ArchiveSource::File(file) => file.read(buf),
+            ArchiveSource::Mapped(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for ArchiveSource {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            ArchiveSource::File(file) => file.seek(pos),
+            ArchiveSource::Mapped(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+// Spreadsheets evaluate cells starting with these as formulas, so matched
+// content like `=cmd|' /C calc'!A0` would run when a CSV report is opened
+const CSV_FORMULA_PREFIXES: &[char] = &['=', '+', '-', '@', '\t', '\r'];
+
+fn sanitize_csv_field(value: &str) -> String {
+    if value.starts_with(CSV_FORMULA_PREFIXES) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+// Picks the encoding for text that is not plain UTF-8: a BOM decides outright;
+// without one, ASCII-range UTF-16 shows up as nulls in every other byte
+fn detect_text_encoding(prefix: &[u8]) -> Option<&'static Encoding> {
+    if let Some((encoding, _)) = Encoding::for_bom(prefix) {
+        return Some(encoding);
+    }
+
+    let sample = &prefix[..prefix.len().min(1024) & !1];
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+
+    let even_nulls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nulls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let mostly = |nulls: usize| nulls * 10 >= pairs * 7;
+    let rarely = |nulls: usize| nulls * 10 <= pairs;
+
+    if mostly(odd_nulls) && rarely(even_nulls) {
+        Some(UTF_16LE)
+    } else if mostly(even_nulls) && rarely(odd_nulls) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+// Decodes BOM-prefixed and UTF-16 text; anything else must already be valid UTF-8
+fn decode_text(bytes: &[u8]) -> Option<Cow<'_, str>> {
+    match detect_text_encoding(bytes) {
+        Some(encoding) => Some(encoding.decode(bytes).0),
+        None => std::str::from_utf8(bytes).ok().map(Cow::Borrowed),
+    }
+}
+
+// Null-byte heuristic over the first KiB: binary files usually have plenty,
+// unless they are UTF-16 text
+fn looks_like_text(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(1024)];
+    if sample.is_empty() {
+        return false;
+    }
+    if detect_text_encoding(sample).is_some() {
+        return true;
+    }
+
+    let null_count = sample.iter().filter(|&&b| b == 0).count();
+    let null_ratio = null_count as f64 / sample.len() as f64;
+
+    // If more than 10% null bytes, probably binary
+    null_ratio < 0.1
+}
+
+// Where a result was found: a file on disk, or an entry inside an on-disk
+// archive (nested levels joined with `!`). Kept apart so archive_path and
+// entry_path never have to be recovered from the display string.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Location {
+    archive: Option<String>,
+    entry: String,
+}
+
+impl Location {
+    fn file(path: impl std::fmt::Display) -> Self {
+        Location { archive: None, entry: path.to_string() }
+    }
+
+    fn entry(archive: impl std::fmt::Display, entry: impl std::fmt::Display) -> Self {
+        Location { archive: Some(archive.to_string()), entry: entry.to_string() }
+    }
+
+    // `name` inside this location, which is itself an archive
+    fn child(&self, name: &str) -> Self {
+        match &self.archive {
+            Some(archive) => Location::entry(archive, format!("{}!{}", self.entry, name)),
+            None => Location::entry(&self.entry, name),
+        }
+    }
+
+    // The on-disk file the location is in
+    fn root(&self) -> &str {
+        self.archive.as_deref().unwrap_or(&self.entry)
+    }
+}
+
+// Every archive level, top-level or nested, is joined to its entry with `!`
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.archive {
+            Some(archive) => write!(f, "{}!{}", archive, self.entry),
+            None => f.write_str(&self.entry),
+        }
+    }
+}
+
+impl SearchResult {
+    // A result at `location`, with the rest of the fields to be filled in
+    fn at(location: &Location) -> Self {
+        SearchResult {
+            file_location: location.to_string(),
+            archive_path: location.archive.clone(),
+            entry_path: location.entry.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+// The --output-format jsonl writer; shared by the workers, so behind a lock
+struct JsonLines(Mutex<Box<dyn Write + Send>>);
+
+impl std::fmt::Debug for JsonLines {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JsonLines")
+    }
+}
+
+// Master mode's input, bucketed by file type
+#[derive(Debug, Default)]
+struct MasterFiles {
+    jar_files: Vec<PathBuf>,
+    zip_files: Vec<PathBuf>,
+    tar_files: Vec<PathBuf>,
+    java_files: Vec<PathBuf>,
+    config_files: Vec<PathBuf>,
+    script_files: Vec<PathBuf>,
+    xml_files: Vec<PathBuf>,
+    text_files: Vec<PathBuf>,
+    unknown_files: Vec<PathBuf>,
+}
+
+impl MasterFiles {
+    fn buckets(&self) -> [(&'static str, &[PathBuf]); 9] {
+        [
+            ("JAR files", &self.jar_files),
+            ("ZIP files", &self.zip_files),
+            ("TAR archives (.tar, .tgz, .tar.gz)", &self.tar_files),
+            ("Java files", &self.java_files),
+            ("Config files (.properties, .conf, .ini)", &self.config_files),
+            ("Script files (.bat, .sh, .py, etc.)", &self.script_files),
+            ("XML files (.xml, .xsd, etc.)", &self.xml_files),
+            ("Text files (.txt, .json, .yaml, etc.)", &self.text_files),
+            ("Other files", &self.unknown_files),
+        ]
+    }
+
+    // Everything that is searched line by line rather than opened as an archive
+    fn other_files(&self) -> Vec<PathBuf> {
+        [&self.config_files, &self.script_files, &self.xml_files, &self.text_files, &self.unknown_files]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    fn total(&self) -> usize {
+        self.buckets().iter().map(|(_, bucket)| bucket.len()).sum()
+    }
+}
+
+// Entry counts and sizes of one JAR for --list
+#[derive(Debug, Default)]
+struct JarContents {
+    classes: usize,
+    java: usize,
+    files: usize,
+    uncompressed: u64,
+    compressed: u64,
+}
+
+impl JarContents {
+    fn ratio(&self) -> f64 {
+        if self.compressed == 0 {
+            return 0.0;
+        }
+        self.uncompressed as f64 / self.compressed as f64
+    }
+}
+
+#[derive(Debug)]
+pub struct JarTool {
+    stats: Arc<Mutex<SearchStats>>,
+    results: Arc<Mutex<Vec<SearchResult>>>,
+    verbose: bool,
+    size_threshold: u64,
+    // Only files last modified inside this window (--modified-after/--modified-before)
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
+    parallel_jobs: usize,
+    excludes: HashSet<String>,
+    // Compiled once; checked against the whole path alongside the substrings (--exclude-regex)
+    exclude_regexes: Vec<Regex>,
+    mini_mode: bool,
+    // Mini mode keeps one result per (file, query), so --queries tags survive
+    unique_files: MiniIndex,
+    // Every top-level file that passed the filters, for --files-without-matches
+    processed_files: Arc<Mutex<HashSet<String>>>,
+    // Local path of every top-level file or archive that produced a result
+    matched_files: Arc<Mutex<HashSet<String>>>,
+    // Files and archives (nested ones included) that could not be opened or read
+    failures: Arc<Mutex<Vec<(PathBuf, String)>>>,
+    max_nesting: usize,
+    accurate_class_names: bool,
+    // Lines kept before and after each text match
+    context_before: usize,
+    context_after: usize,
+    show_progress: bool,
+    // Only print results: no banners, no stats block
+    quiet: bool,
+    // Memory-map archives instead of reading them through the file (--mmap)
+    use_mmap: bool,
+    // Print each result as soon as it is found (--stream)
+    stream: bool,
+    // Where each result is written as one JSON object the moment it is found (--output-format jsonl)
+    json_lines: Option<JsonLines>,
+    // Set once a failed JSON-lines write has been reported
+    json_lines_failed: AtomicBool,
+    pool: rayon::ThreadPool,
+    includes: Option<GlobSet>,
+    // Matched against full entry names inside archives only (--entry-glob)
+    entry_globs: Option<GlobSet>,
+    respect_gitignore: bool,
+    // Neutralise spreadsheet formulas in CSV exports (off with --no-csv-sanitize)
+    csv_sanitize: bool,
+    follow_symlinks: bool,
+    // Descend into dot-files and dot-directories while walking (--exclude-hidden turns this off)
+    include_hidden: bool,
+    // Levels below the search directory the walk descends (--max-depth)
+    max_depth: Option<usize>,
+    // Explicit paths (--stdin) that replace the directory walk
+    file_list: Option<Vec<PathBuf>>,
+    // Local copy -> the name reported instead of it (downloaded archives' URLs)
+    display_names: Vec<(String, String)>,
+    string_constants_only: bool,
+    // Strip surrounding whitespace from matched text lines (off with --no-trim)
+    trim_lines: bool,
+    ignore_case: bool,
+    // Report text lines that do NOT match (--invert-match)
+    invert_match: bool,
+    // Match whole words / whole class name segments only (--word)
+    whole_word: bool,
+    // Lowercase extension -> match_type label, over the built-in names (--type-map)
+    type_map: HashMap<String, String>,
+    min_string_len: usize,
+    max_results: Option<usize>,
+    // Characters of line_content kept when printing or exporting (--max-line-length)
+    max_line_length: Option<usize>,
+    // Results collected so far, checked lock-free before each file for --max-results
+    results_added: AtomicUsize,
+    // Stop searching an archive once it has one result (--first-per-archive)
+    first_per_archive: bool,
+    // Top-level archives that already have their result
+    archives_hit: Mutex<HashSet<String>>,
+    timeout_per_file: Option<Duration>,
+    // Uncompressed/compressed ratio above which --list flags a JAR
+    bomb_ratio: f64,
+    // Digest shown for each JAR by list_jars (--checksums)
+    list_checksums: Option<ChecksumAlgorithm>,
+    // Archives listing more entries than this are skipped (--max-entries)
+    max_entries: Option<usize>,
+    // Tried on encrypted archive entries (--zip-password)
+    zip_password: Option<String>,
+    // Shell command each candidate result is piped to; kept on exit 0 (--filter-command)
+    filter_command: Option<String>,
+    // Inclusive window of line numbers text results must fall in (--min-line/--max-line)
+    min_line: Option<usize>,
+    max_line: Option<usize>,
+    // Set once a failure to start the filter command has been reported
+    filter_failed: AtomicBool,
+    // Class lists of unchanged JARs from earlier runs (--cache)
+    class_cache: Option<ClassCache>,
+    // Set by sort_results; otherwise CSV export orders rows by location itself
+    results_sorted: AtomicBool,
+}
+
+impl JarTool {
+    pub fn new(verbose: bool, size_threshold: u64, parallel_jobs: Option<usize>, excludes: Vec<String>, mini_mode: bool) -> Result<Self, JarToolError> {
+        // No setting, or 0, means one job per CPU core
+        let cpus = num_cpus::get();
+        let jobs = parallel_jobs.filter(|&n| n > 0).unwrap_or(cpus);
+        if jobs > cpus * 4 {
+            eprintln!("{} {} parallel jobs on {} CPU cores; beyond about 4x the cores threads only contend", "WARNING".yellow(), jobs, cpus);
+        }
+        // A pool per instance: the global pool can only be initialised once per process
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .map_err(|source| JarToolError::ThreadPool { jobs, source })?;
+
+        let exclude_set: HashSet<String> = excludes.into_iter().collect();
+
+        let tool = Self {
+            stats: Arc::new(Mutex::new(SearchStats::default())),
+            results: Arc::new(Mutex::new(Vec::new())),
+            verbose,
+            size_threshold,
+            modified_after: None,
+            modified_before: None,
+            parallel_jobs: jobs,
+            excludes: exclude_set,
+            exclude_regexes: Vec::new(),
+            mini_mode,
+            unique_files: Arc::new(Mutex::new(HashSet::new())),
+            processed_files: Arc::new(Mutex::new(HashSet::new())),
+            matched_files: Arc::new(Mutex::new(HashSet::new())),
+            failures: Arc::new(Mutex::new(Vec::new())),
+            max_nesting: 2,
+            accurate_class_names: false,
+            context_before: 0,
+            context_after: 0,
+            show_progress: false,
+            quiet: false,
+            use_mmap: false,
+            stream: false,
+            json_lines: None,
+            json_lines_failed: AtomicBool::new(false),
+            pool,
+            includes: None,
+            entry_globs: None,
+            respect_gitignore: false,
+            csv_sanitize: true,
+            follow_symlinks: false,
+            include_hidden: true,
+            max_depth: None,
+            file_list: None,
+            display_names: Vec::new(),
+            string_constants_only: false,
+            trim_lines: true,
+            ignore_case: false,
+            invert_match: false,
+            whole_word: false,
+            type_map: HashMap::new(),
+            min_string_len: DEFAULT_MIN_STRING_LEN,
+            max_results: None,
+            max_line_length: Some(DEFAULT_MAX_LINE_LENGTH),
+            results_added: AtomicUsize::new(0),
+            first_per_archive: false,
+            archives_hit: Mutex::new(HashSet::new()),
+            timeout_per_file: None,
+            bomb_ratio: DEFAULT_BOMB_RATIO,
+            list_checksums: None,
+            max_entries: None,
+            zip_password: None,
+            filter_command: None,
+            min_line: None,
+            max_line: None,
+            filter_failed: AtomicBool::new(false),
+            class_cache: None,
+            results_sorted: AtomicBool::new(false),
+        };
+        tool.log_verbose(&format!("Using {} worker threads ({} CPU cores)", jobs, cpus));
+        Ok(tool)
+    }
+
+    pub fn with_max_nesting(mut self, max_nesting: usize) -> Self {
+        self.max_nesting = max_nesting;
+        self
+    }
+
+    /// Only processes files whose modification time falls in the window;
+    /// either end may be open.
+    pub fn with_modified_window(mut self, after: Option<SystemTime>, before: Option<SystemTime>) -> Self {
+        self.modified_after = after;
+        self.modified_before = before;
+        self
+    }
+
+    pub fn with_accurate_class_names(mut self, accurate: bool) -> Self {
+        self.accurate_class_names = accurate;
+        self
+    }
+
+    /// Captures up to `before` lines ahead of and `after` lines following each
+    /// text match.
+    pub fn with_context_lines(mut self, before: usize, after: usize) -> Self {
+        self.context_before = before;
+        self.context_after = after;
+        self
+    }
+
+    /// Restricts master mode to files (and archive entries) matching any of the globs.
+    pub fn with_includes(mut self, patterns: &[String]) -> Result<Self, globset::Error> {
+        if patterns.is_empty() {
+            return Ok(self);
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        self.includes = Some(builder.build()?);
+        Ok(self)
+    }
+
+    /// Searches only the archive entries whose full name (e.g.
+    /// `BOOT-INF/classes/application.yml`) matches one of the globs. Nested
+    /// archives are still opened; their entries are matched by their own names.
+    pub fn with_entry_globs(mut self, patterns: &[String]) -> Result<Self, globset::Error> {
+        if patterns.is_empty() {
+            return Ok(self);
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        self.entry_globs = Some(builder.build()?);
+        Ok(self)
+    }
+
+    /// Also excludes paths matching any of these regexes (searched anywhere in
+    /// the path, like `--exclude` substrings).
+    pub fn with_exclude_regexes(mut self, patterns: &[String]) -> Result<Self, regex::Error> {
+        self.exclude_regexes = patterns.iter().map(|p| Regex::new(p)).collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    pub fn with_respect_gitignore(mut self, respect: bool) -> Self {
+        self.respect_gitignore = respect;
+        self
+    }
+
+    /// Controls whether CSV cells that would start a spreadsheet formula are
+    /// prefixed with a single quote. On by default.
+    pub fn with_csv_sanitize(mut self, sanitize: bool) -> Self {
+        self.csv_sanitize = sanitize;
+        self
+    }
+
+    /// Follows symbolic links while walking directories. Cycles are cut and a
+    /// file reachable through several links is scanned once.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Controls whether walking a directory visits files and directories whose
+    /// name starts with `.` (`.git`, `.idea`, ...). On by default; the directory
+    /// being searched and explicitly listed paths are never skipped.
+    pub fn with_include_hidden(mut self, include: bool) -> Self {
+        self.include_hidden = include;
+        self
+    }
+
+    /// Limits how deep directory walks descend: 1 searches only the files
+    /// directly in the directory, 2 also its subdirectories, and so on. Links
+    /// followed with `with_follow_symlinks` count as a level like any other
+    /// directory. Unlimited by default.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Processes exactly these paths instead of walking the search directory.
+    /// Directories in the list are walked as usual.
+    pub fn with_file_list(mut self, files: Option<Vec<PathBuf>>) -> Self {
+        self.file_list = files;
+        self
+    }
+
+    /// Reports results and failures under each file's given name rather than
+    /// its local path, e.g. the URL an archive was downloaded from.
+    pub fn with_display_names(mut self, names: Vec<(PathBuf, String)>) -> Self {
+        self.display_names = names.into_iter().map(|(path, name)| (path.display().to_string(), name)).collect();
+        self
+    }
+
+    // `location` with a local path that has a display name swapped for it
+    fn display_location(&self, location: String) -> String {
+        for (path, name) in &self.display_names {
+            if let Some(rest) = location.strip_prefix(path.as_str()) {
+                if rest.is_empty() || rest.starts_with('!') {
+                    return format!("{}{}", name, rest);
+                }
+            }
+        }
+        location
+    }
+
+    /// Matches class files against their string literals only, instead of every
+    /// printable run in the bytecode.
+    pub fn with_string_constants(mut self, string_constants_only: bool) -> Self {
+        self.string_constants_only = string_constants_only;
+        self
+    }
+
+    /// Whether matched text lines are stored with leading and trailing
+    /// whitespace removed (the default). Turn off where indentation matters,
+    /// e.g. YAML, Python or Makefiles.
+    pub fn with_trim(mut self, trim_lines: bool) -> Self {
+        self.trim_lines = trim_lines;
+        self
+    }
+
+    fn result_line<'a>(&self, line: &'a str) -> &'a str {
+        if self.trim_lines {
+            line.trim()
+        } else {
+            line
+        }
+    }
+
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Reports every text line that does not match instead of those that do.
+    /// Binary and bytecode scans are unaffected.
+    pub fn with_invert_match(mut self, invert_match: bool) -> Self {
+        self.invert_match = invert_match;
+        self
+    }
+
+    /// Content and master regexes only match whole words (`\b(?:PATTERN)\b`);
+    /// class substring searches only match whole `.`/`/`-separated segments.
+    pub fn with_whole_word(mut self, whole_word: bool) -> Self {
+        self.whole_word = whole_word;
+        self
+    }
+
+    /// Custom `match_type` labels by file extension (`tf` -> `terraform`),
+    /// taking precedence over the built-in names. A leading `.` is ignored and
+    /// later entries for the same extension win.
+    pub fn with_type_map<I: IntoIterator<Item = (String, String)>>(mut self, entries: I) -> Self {
+        for (ext, label) in entries {
+            self.type_map.insert(ext.trim_start_matches('.').to_lowercase(), label);
+        }
+        self
+    }
+
+    /// Shortest printable run reported by the strings-style binary scan.
+    pub fn with_min_string_len(mut self, min_len: usize) -> Self {
+        self.min_string_len = min_len.max(1);
+        self
+    }
+
+    /// Cuts `line_content` (and context lines) to this many characters when
+    /// printing and exporting, marking how much was dropped; `None` keeps
+    /// lines whole. Matching and --filter-command always see the full line.
+    pub fn with_max_line_length(mut self, max_line_length: Option<usize>) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    // `text` cut to --max-line-length with a `…(+N chars)` marker, and the byte
+    // offset of the cut (the whole length when nothing was cut)
+    fn truncate_line<'a>(&self, text: &'a str) -> (Cow<'a, str>, usize) {
+        let Some(max) = self.max_line_length else {
+            return (Cow::Borrowed(text), text.len());
+        };
+        match text.char_indices().nth(max) {
+            Some((cut, _)) => (Cow::Owned(format!("{}…(+{} chars)", &text[..cut], text[cut..].chars().count())), cut),
+            None => (Cow::Borrowed(text), text.len()),
+        }
+    }
+
+    // A result's line as printed or exported: truncated, with its match spans
+    // clipped to the cut so none covers the marker
+    fn truncated_line<'a>(&self, result: &'a SearchResult) -> (Cow<'a, str>, Vec<(usize, usize)>) {
+        let (line, cut) = self.truncate_line(&result.line_content);
+        (line, clip_spans(&result.match_spans, cut))
+    }
+
+    // Copies of `results` with long lines truncated, for the exporters; borrowed
+    // when nothing needs cutting
+    fn truncated_results<'a>(&self, results: &'a [SearchResult]) -> Cow<'a, [SearchResult]> {
+        let too_long = |text: &str| self.truncate_line(text).1 < text.len();
+        let needs_cut = |r: &SearchResult| too_long(&r.line_content) || r.context.iter().flatten().any(|line| too_long(line));
+        if !results.iter().any(needs_cut) {
+            return Cow::Borrowed(results);
+        }
+
+        Cow::Owned(
+            results
+                .iter()
+                .map(|result| {
+                    let mut result = result.clone();
+                    let (line, spans) = self.truncated_line(&result);
+                    (result.line_content, result.match_spans) = (line.into_owned(), spans);
+                    if let Some(context) = result.context.as_mut() {
+                        for line in context.iter_mut() {
+                            *line = self.truncate_line(line).0.into_owned();
+                        }
+                    }
+                    result
+                })
+                .collect(),
+        )
+    }
+
+    /// Stops starting new files once this many results are collected. Files
+    /// already in flight finish, so parallel runs may return slightly more.
+    pub fn with_max_results(mut self, max_results: Option<usize>) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Keeps only the first result found in each archive and skips its
+    /// remaining entries. Unlike mini mode this saves the scanning time, not
+    /// just the output; which entry "first" is varies between parallel runs.
+    pub fn with_first_per_archive(mut self, first_per_archive: bool) -> Self {
+        self.first_per_archive = first_per_archive;
+        self
+    }
+
+    // Whether the archive containing `location` needs no more searching
+    fn archive_done(&self, location: &Location) -> bool {
+        if !self.first_per_archive {
+            return false;
+        }
+        self.archives_hit.lock().is_ok_and(|hit| hit.contains(location.root()))
+    }
+
+    /// Abandons a file (with a warning) once reading it takes longer than
+    /// `timeout`, so malformed or hostile archives cannot stall a scan.
+    pub fn with_timeout_per_file(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout_per_file = timeout;
+        self
+    }
+
+    /// Compression ratio above which `list_jars` flags a JAR as a possible zip bomb.
+    pub fn with_bomb_ratio(mut self, ratio: f64) -> Self {
+        self.bomb_ratio = ratio;
+        self
+    }
+
+    /// Adds a column with each JAR's digest to `list_jars`.
+    pub fn with_list_checksums(mut self, algorithm: Option<ChecksumAlgorithm>) -> Self {
+        self.list_checksums = algorithm;
+        self
+    }
+
+    /// Skips any archive (nested ones included) whose central directory lists
+    /// more than this many entries, rather than iterating them all.
+    pub fn with_max_entries(mut self, max_entries: Option<usize>) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Password used to decrypt ZipCrypto/AES-encrypted archive entries.
+    /// Without one, or when it is wrong, such entries are reported as
+    /// `encrypted_entry` results instead of being searched.
+    pub fn with_zip_password(mut self, password: Option<String>) -> Self {
+        self.zip_password = password;
+        self
+    }
+
+    /// Only keeps results for which `command`, run through the shell with the
+    /// matched line on stdin, exits 0. The file, line and match type are also
+    /// passed as `JARTOOL_FILE`, `JARTOOL_LINE` and `JARTOOL_MATCH_TYPE`. One
+    /// process per candidate, at most one per worker thread at a time.
+    pub fn with_filter_command(mut self, command: Option<String>) -> Self {
+        self.filter_command = command;
+        self
+    }
+
+    /// Drops text results whose line number falls outside `min..=max`, e.g.
+    /// to skip license headers. Results without a line number (class names,
+    /// binary and bytecode hits) are kept.
+    pub fn with_line_range(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_line = min;
+        self.max_line = max;
+        self
+    }
+
+    /// Keeps the class list of every JAR a class-name search reads in an
+    /// index under `dir`, reused on later runs while the JAR's size and
+    /// modification time are unchanged. Written back by [`Self::save_cache`].
+    pub fn with_cache(mut self, dir: Option<&Path>) -> Result<Self, JarToolError> {
+        self.class_cache = dir.map(ClassCache::open).transpose()?;
+        Ok(self)
+    }
+
+    pub fn save_cache(&self) -> Result<(), JarToolError> {
+        match &self.class_cache {
+            Some(cache) => cache.save(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn with_progress(mut self, show_progress: bool) -> Self {
+        self.show_progress = show_progress;
+        self
+    }
+
+    /// Opens JARs and ZIPs through a memory mapping rather than buffered file
+    /// reads, in every mode. Files that cannot be mapped are read instead.
+    pub fn with_mmap(mut self, use_mmap: bool) -> Self {
+        self.use_mmap = use_mmap;
+        self
+    }
+
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Prints results as they are found instead of only collecting them for
+    /// `print_results`; stats and exports still see every result.
+    pub fn with_stream(mut self, stream: bool) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// Writes every result to `filename` ('-' for stdout) as one JSON object per
+    /// line (NDJSON) as soon as it is found, for log pipelines and `jq`.
+    pub fn with_json_lines(mut self, filename: Option<&str>) -> Result<Self, JarToolError> {
+        self.json_lines = filename.map(create_output).transpose()?.map(|output| JsonLines(Mutex::new(output)));
+        Ok(self)
+    }
+
+    // Runs `f` over the files in parallel, ticking a progress bar as each one completes
+    fn for_each_with_progress<T, F>(&self, files: &[T], f: F)
+    where
+        T: Sync,
+        F: Fn(&T) + Send + Sync,
+    {
+        let progress = if self.show_progress {
+            let bar = ProgressBar::new(files.len() as u64);
+            bar.set_style(
+                ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} files (ETA {eta}) {msg}")
+                    .unwrap_or_else(|_| ProgressStyle::default_bar())
+                    .progress_chars("=> "),
+            );
+            bar
+        } else {
+            ProgressBar::hidden()
+        };
+
+        // Err(()) is the "--max-results reached" sentinel that stops handing out files
+        let _ = self.pool.install(|| {
+            files.par_iter().try_for_each(|file| {
+                if self.result_limit_reached() {
+                    return Err(());
+                }
+                f(file);
+                progress.inc(1);
+                if self.show_progress {
+                    let matches = self.stats.lock().map(|s| s.matches_found).unwrap_or(0);
+                    progress.set_message(format!("{} matches", matches));
+                }
+                Ok(())
+            })
+        });
+
+        progress.finish_and_clear();
+    }
+
+
+    fn should_exclude_path(&self, path: &Path) -> bool {
+        let path_str = path.to_string_lossy();
+        for exclude in &self.excludes {
+            if path_str.contains(exclude) {
+                self.log_verbose(&format!("Excluding path: {} (matches: {})", path_str, exclude));
+                return true;
+            }
+        }
+        if let Some(regex) = self.exclude_regexes.iter().find(|regex| regex.is_match(&path_str)) {
+            self.log_verbose(&format!("Excluding path: {} (matches regex: {})", path_str, regex));
+            return true;
+        }
+        false
+    }
+
+    fn should_include_path(&self, path: &Path, search_dir: &Path) -> bool {
+        let Some(includes) = &self.includes else {
+            return true;
+        };
+        let relative = path.strip_prefix(search_dir).unwrap_or(path);
+        includes.is_match(relative) || path.file_name().is_some_and(|name| includes.is_match(name))
+    }
+
+    fn should_include_entry(&self, entry_name: &str) -> bool {
+        if self.entry_globs.as_ref().is_some_and(|globs| !globs.is_match(entry_name)) {
+            return false;
+        }
+        let Some(includes) = &self.includes else {
+            return true;
+        };
+        let file_name = entry_name.rsplit('/').next().unwrap_or(entry_name);
+        includes.is_match(entry_name) || includes.is_match(file_name)
+    }
+
+    // Reads an archive's central directory, refusing it when it lists more
+    // entries than --max-entries allows
+    fn zip_archive<R: Read + Seek>(&self, reader: R, location: impl std::fmt::Display) -> zip::result::ZipResult<ZipArchive<R>> {
+        let archive = ZipArchive::new(reader).inspect_err(|e| self.record_failure(location.to_string(), e))?;
+        if let Some(max) = self.max_entries.filter(|&max| archive.len() > max) {
+            eprintln!("{} Skipping {}: {} entries exceed --max-entries {}", "WARNING".yellow(), location, archive.len(), max);
+            self.record_failure(location.to_string(), format!("{} entries exceed --max-entries {}", archive.len(), max));
+            return Err(zip::result::ZipError::InvalidArchive("too many entries"));
+        }
+        Ok(archive)
+    }
+
+    // Opens entry `i` (decrypting it with --zip-password if set) and hands it
+    // to `f`. Encrypted entries that cannot be opened are reported rather than
+    // silently skipped; other unreadable entries yield None.
+    fn with_entry<R: Read + Seek, T>(&self, archive: &mut ZipArchive<R>, i: usize, location: &Location, f: impl FnOnce(&mut ZipFile) -> T) -> Option<T> {
+        // Scoped so the entry's borrow of the archive ends before the name lookup
+        let reason = {
+            let opened = match &self.zip_password {
+                Some(password) => archive.by_index_decrypt(i, password.as_bytes()),
+                None => archive.by_index(i).map(Ok),
+            };
+            match opened {
+                Ok(Ok(mut file)) => return Some(f(&mut file)),
+                Ok(Err(zip::result::InvalidPassword)) => "wrong --zip-password",
+                Err(zip::result::ZipError::UnsupportedArchive(zip::result::ZipError::PASSWORD_REQUIRED)) => "password required (use --zip-password)",
+                Err(_) => return None,
+            }
+        };
+
+        // The raw entry needs no password, so its name is still readable
+        let name = archive.by_index_raw(i).ok()?.name().to_string();
+        self.log_verbose(&format!("Cannot decrypt {} in {}: {}", name, location, reason));
+        self.add_result(SearchResult {
+            line_number: None,
+            line_content: format!("Encrypted entry not searched: {}", reason),
+            match_type: "encrypted_entry".to_string(),
+            ..SearchResult::at(&location.child(&name))
+        });
+        None
+    }
+
+    fn record_failure(&self, path: impl Into<PathBuf>, error: impl std::fmt::Display) {
+        let path = PathBuf::from(self.display_location(path.into().display().to_string()));
+        self.log_verbose(&format!("Cannot read {}: {}", path.display(), error));
+        if let Ok(mut failures) = self.failures.lock() {
+            failures.push((path, error.to_string()));
+        }
+    }
+
+    /// Files and archives that could not be opened or were not valid
+    /// archives, sorted by path; each path is listed once, with its first error.
+    pub fn failures(&self) -> Vec<(PathBuf, String)> {
+        let mut failures = self.failures.lock().map(|f| f.clone()).unwrap_or_default();
+        failures.sort_by(|a, b| a.0.cmp(&b.0));
+        failures.dedup_by(|later, earlier| later.0 == earlier.0);
+        failures
+    }
+
+    // Wraps a per-file reader in the --timeout-per-file budget, which starts now
+    fn timed<R: Read>(&self, inner: R, location: impl std::fmt::Display) -> TimedReader<R> {
+        let budget = self.timeout_per_file.map(|timeout| {
+            Arc::new(ReadBudget {
+                deadline: Instant::now() + timeout,
+                location: location.to_string(),
+                expired: AtomicBool::new(false),
+            })
+        });
+        TimedReader { inner, budget }
+    }
+
+    fn open_file(&self, path: &Path) -> std::io::Result<TimedReader<File>> {
+        let file = File::open(path).inspect_err(|e| self.record_failure(path, e))?;
+        Ok(self.timed(file, path.display()))
+    }
+
+    // Opens a JAR/ZIP for ZipArchive, memory-mapped with --mmap; falls back to
+    // plain reads when the file cannot be mapped (e.g. some network filesystems)
+    fn open_archive(&self, path: &Path) -> std::io::Result<TimedReader<ArchiveSource>> {
+        let file = File::open(path).inspect_err(|e| self.record_failure(path, e))?;
+        let source = if self.use_mmap {
+            // SAFETY: the mapping is read-only and only ever read as plain bytes, but it
+            // is not protected against other processes: if the file is truncated while
+            // mapped, touching the lost pages raises SIGBUS and kills the process. That
+            // risk is why mapping is opt-in; plain reads just see a short file and fail.
+            match unsafe { Mmap::map(&file) } {
+                Ok(mmap) => ArchiveSource::Mapped(Cursor::new(SharedMmap(Arc::new(mmap)))),
+                Err(e) => {
+                    self.log_verbose(&format!("Cannot mmap {}, reading it instead: {}", path.display(), e));
+                    ArchiveSource::File(ArchiveFile::new(path, file))
+                }
+            }
+        } else {
+            ArchiveSource::File(ArchiveFile::new(path, file))
+        };
+        Ok(self.timed(source, path.display()))
+    }
+
+    fn compile_patterns(&self, patterns: &[String]) -> Result<PatternSet, regex::Error> {
+        if !self.whole_word {
+            return PatternSet::new(patterns, self.ignore_case);
+        }
+
+        // Non-capturing, so alternations and groups in the user's regex stay intact;
+        // results are still tagged with the pattern as given
+        let wrapped: Vec<String> = patterns.iter().map(|p| format!(r"\b(?:{})\b", p)).collect();
+        let mut set = PatternSet::new(&wrapped, self.ignore_case)?;
+        set.labels = patterns.to_vec();
+        Ok(set)
+    }
+
+    // Whether a text line is reported: it matches, or with --invert-match it does not
+    fn line_selected(&self, patterns: &PatternSet, line: &str) -> bool {
+        patterns.is_match(line) != self.invert_match
+    }
+
+    fn log_verbose(&self, msg: &str) {
+        if self.verbose {
+            eprintln!("{} {}", "[DEBUG]".blue(), msg);
+        }
+    }
+
+    // Progress banners (INFO, PHASE, SUCCESS, ...); silenced by --quiet
+    fn log_status(&self, msg: &str) {
+        if !self.quiet {
+            eprintln!("{}", msg);
+        }
+    }
+
+    fn update_stats<F>(&self, updater: F)
+    where
+        F: FnOnce(&mut SearchStats),
+    {
+        if let Ok(mut stats) = self.stats.lock() {
+            updater(&mut stats);
+        }
+    }
+
+    // Records how long a master-search phase took; a rerun (--watch) replaces
+    // the earlier timing rather than adding a second row
+    fn record_phase(&self, phase: &str, start: Instant) {
+        let duration = start.elapsed();
+        self.update_stats(|stats| {
+            stats.phase_times.retain(|(name, _)| name != phase);
+            stats.phase_times.push((phase.to_string(), duration));
+        });
+    }
+
+    fn add_bytes_scanned(&self, bytes: usize) {
+        self.update_stats(|stats| stats.bytes_scanned += bytes as u64);
+    }
+
+    /// Number of results collected so far (unique files in mini mode).
+    pub fn result_count(&self) -> usize {
+        self.results.lock().map(|r| r.len()).unwrap_or(0)
+    }
+
+    // Results recorded after `first`, i.e. those produced by the current search
+    fn results_since(&self, first: usize) -> Vec<SearchResult> {
+        self.results.lock().map(|r| r.get(first..).unwrap_or_default().to_vec()).unwrap_or_default()
+    }
+
+    /// All results collected so far by this instance.
+    pub fn results(&self) -> Vec<SearchResult> {
+        self.results.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    /// Sorts the collected results for reproducible output: `path`, `type` or
+    /// `line`, with file location (then line) breaking ties.
+    pub fn sort_results(&self, by: &str) -> Result<(), JarToolError> {
+        let mut results = self.results.lock().map_err(|_| JarToolError::Poisoned("results"))?;
+        match by {
+            "path" => results.sort_by(by_location),
+            "type" => results.sort_by(|a, b| a.match_type.cmp(&b.match_type).then_with(|| by_location(a, b))),
+            "line" => results.sort_by(|a, b| a.line_number.cmp(&b.line_number).then_with(|| by_location(a, b))),
+            _ => return Err(JarToolError::InvalidArgument(format!("unknown sort key '{}': expected path, type or line", by))),
+        }
+        self.results_sorted.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Drops results repeating an earlier one's location, line and content,
+    /// keeping first-seen order. Returns how many were removed.
+    pub fn dedupe_results(&self) -> Result<usize, JarToolError> {
+        let mut results = self.results.lock().map_err(|_| JarToolError::Poisoned("results"))?;
+        let before = results.len();
+
+        let mut seen = HashSet::new();
+        results.retain(|r| seen.insert((r.file_location.clone(), r.line_number, r.line_content.clone())));
+        Ok(before - results.len())
+    }
+
+    /// Number of results per `match_type`, most frequent first.
+    pub fn match_type_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        if let Ok(results) = self.results.lock() {
+            for result in results.iter() {
+                *counts.entry(result.match_type.clone()).or_default() += 1;
+            }
+        }
+
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Scanned files that produced no result, sorted. Archive results count for
+    /// the archive itself (`app.jar!com/Foo.class` marks `app.jar` as matched).
+    pub fn files_without_matches(&self) -> Vec<String> {
+        let processed = self.processed_files.lock().map(|p| p.clone()).unwrap_or_default();
+        let matched = self.matched_files.lock().map(|m| m.clone()).unwrap_or_default();
+
+        let mut unmatched: Vec<String> = processed
+            .difference(&matched)
+            .map(|path| self.display_location(path.clone()))
+            .collect();
+        unmatched.sort();
+        unmatched
+    }
+
+    // Files with matches in mini mode; a file hit by several queries counts once
+    fn unique_file_count(&self) -> usize {
+        self.unique_files
+            .lock()
+            .map(|unique| unique.iter().map(|(file, _)| file).collect::<HashSet<_>>().len())
+            .unwrap_or(0)
+    }
+
+    pub fn stats(&self) -> SearchStats {
+        self.stats.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    pub fn result_limit_reached(&self) -> bool {
+        self.max_results.is_some_and(|max| self.results_added.load(Ordering::Relaxed) >= max)
+    }
+
+    fn add_result(&self, mut result: SearchResult) {
+        if let Some(line) = result.line_number {
+            if self.min_line.is_some_and(|min| line < min) || self.max_line.is_some_and(|max| line > max) {
+                return;
+            }
+        }
+
+        // Local path, before any display name replaces it; archive_done() and
+        // files_without_matches() are both asked about local paths
+        let local_path = result.archive_path.clone().unwrap_or_else(|| result.entry_path.clone());
+        let archive = match self.first_per_archive {
+            true => result.archive_path.clone(),
+            false => None,
+        };
+
+        if !self.display_names.is_empty() {
+            result.file_location = self.display_location(std::mem::take(&mut result.file_location));
+            match &mut result.archive_path {
+                Some(archive) => *archive = self.display_location(std::mem::take(archive)),
+                None => result.entry_path = self.display_location(std::mem::take(&mut result.entry_path)),
+            }
+        }
+
+        if !self.filter_accepts(&result) {
+            return;
+        }
+        if let Ok(mut matched) = self.matched_files.lock() {
+            matched.insert(local_path);
+        }
+
+        // Parallel workers may each find a match before the archive is marked
+        if let Some(archive) = archive {
+            if !self.archives_hit.lock().is_ok_and(|mut hit| hit.insert(archive)) {
+                return;
+            }
+        }
+
+        if self.mini_mode {
+            // In mini mode, only add each file once per query
+            let file_location = result.file_location.clone();
+            if let Ok(mut unique_files) = self.unique_files.lock() {
+                if unique_files.insert((file_location.clone(), result.query.clone())) {
+                    // This is a new file, add a simplified result
+                    let mini_result = SearchResult {
+                        file_location,
+                        archive_path: result.archive_path,
+                        entry_path: result.entry_path,
+                        line_number: None,
+                        line_content: "Found matches".to_string(),
+                        match_type: result.match_type,
+                        query: result.query,
+                        ..Default::default()
+                    };
+                    self.push_result(mini_result);
+                }
+            }
+        } else {
+            // Normal mode, add all results
+            self.push_result(result);
+        }
+        self.update_stats(|stats| stats.matches_found += 1);
+    }
+
+    fn filter_accepts(&self, result: &SearchResult) -> bool {
+        let Some(command) = &self.filter_command else {
+            return true;
+        };
+
+        let mut shell = if cfg!(windows) {
+            let mut shell = std::process::Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = std::process::Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        let child = shell
+            .arg(command)
+            .env("JARTOOL_FILE", &result.file_location)
+            .env("JARTOOL_LINE", result.line_number.map(|n| n.to_string()).unwrap_or_default())
+            .env("JARTOOL_MATCH_TYPE", &result.match_type)
+            .stdin(std::process::Stdio::piped())
+            // Anything the command prints would be mixed into the results
+            .stdout(std::process::Stdio::null())
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                if !self.filter_failed.swap(true, Ordering::Relaxed) {
+                    eprintln!("{} Cannot run --filter-command `{}`: {}", "WARNING".yellow(), command, e);
+                }
+                return false;
+            }
+        };
+
+        // A command that decides without reading its input closes the pipe early
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "{}", result.line_content);
+        }
+        child.wait().is_ok_and(|status| status.success())
+    }
+
+    // With --stream the result is printed while the results lock is held, so
+    // concurrent workers never interleave their lines and numbering stays in order
+    fn push_result(&self, result: SearchResult) {
+        if let Ok(mut results) = self.results.lock() {
+            if self.stream {
+                let shown_through = match result.context {
+                    Some(_) => results.iter().rev().find(|r| r.file_location == result.file_location).map_or(0, last_context_line),
+                    None => 0,
+                };
+                self.print_result(results.len() + 1, &result, shown_through);
+            }
+            if let Some(output) = &self.json_lines {
+                self.write_json_line(output, &result);
+            }
+            results.push(result);
+            self.results_added.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Flushed per line so readers downstream see each result right away
+    fn write_json_line(&self, output: &JsonLines, result: &SearchResult) {
+        let written = output.0.lock().map_err(|_| JarToolError::Poisoned("output")).and_then(|mut output| -> Result<(), JarToolError> {
+            serde_json::to_writer(&mut *output, &self.truncated_results(std::slice::from_ref(result))[0])?;
+            writeln!(output)?;
+            output.flush()?;
+            Ok(())
+        });
+
+        if let Err(e) = written {
+            if !self.json_lines_failed.swap(true, Ordering::Relaxed) {
+                eprintln!("{} Cannot write JSON lines: {}", "WARNING".yellow(), e);
+            }
+        }
+    }
+
+    /// Flushes the `--output-format jsonl` output once the search is done.
+    pub fn finish_json_lines(&self, filename: &str) -> Result<(), JarToolError> {
+        if let Some(output) = &self.json_lines {
+            output.0.lock().map_err(|_| JarToolError::Poisoned("output"))?.flush()?;
+            if filename != "-" {
+                self.log_status(&format!("{} Results exported to {}", "SUCCESS".green(), filename));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn search_exact_class(&self, query: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting exact class search for: {}", query));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let query = self.fold_case(query);
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_class_in_jar(jar_path, |class_name| self.class_name_matches(class_name, &query, true));
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Finds a class and summarises it from bytecode, `javap -p` style: the
+    /// declaration, then each field and method signature as its own result.
+    pub fn inspect_class(&self, query: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting class inspection for: {}", query));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let query = self.fold_case(query);
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.inspect_class_in_jar(jar_path, &query);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    pub fn search_class_substring(&self, query: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting class substring search for: {}", query));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let query = self.fold_case(query);
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_class_in_jar(jar_path, |class_name| self.class_name_matches(class_name, &query, false));
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    pub fn search_package(&self, package: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting package search for: {}", package));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        let package_path = package.replace('.', "/");
+        
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_package_in_jar(jar_path, &package_path);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Finds classes whose constant pool references `target`; with
+    /// `transitive`, also the classes referencing those, and so on. Each
+    /// result is one `A -> B (kinds)` edge of the reverse-dependency graph.
+    pub fn search_references(&self, target: &str, transitive: bool, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting reverse dependency search for: {}", target));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let target_internal = classfile::to_internal_name(target);
+
+        if !transitive {
+            self.for_each_with_progress(&jar_files, |jar_path| {
+                self.search_references_in_jar(jar_path, &target_internal);
+            });
+            self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+            return Ok(self.results_since(first_result));
+        }
+
+        let classes: Mutex<Vec<ClassRefs>> = Mutex::new(Vec::new());
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.collect_class_refs(jar_path, &classes);
+        });
+        let mut classes = classes.into_inner().map_err(|_| JarToolError::Poisoned("class index"))?;
+        // JARs finish in any order; sorting keeps the edge order stable between runs
+        classes.sort_by(|a, b| a.location.cmp(&b.location));
+
+        // Referenced class -> (referencing class, distinct reference kinds)
+        let mut referrers: HashMap<&str, Vec<(usize, BTreeSet<&str>)>> = HashMap::new();
+        for (i, class) in classes.iter().enumerate() {
+            let mut kinds_by_target: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+            for (referenced, kind) in &class.refs {
+                if *referenced != class.name {
+                    kinds_by_target.entry(referenced).or_default().insert(kind);
+                }
+            }
+            for (referenced, kinds) in kinds_by_target {
+                referrers.entry(referenced).or_default().push((i, kinds));
+            }
+        }
+
+        // Breadth-first outwards from the target, one result per edge
+        let mut queue: VecDeque<&str> = VecDeque::from([target_internal.as_str()]);
+        let mut visited: HashSet<&str> = HashSet::from([target_internal.as_str()]);
+        while let Some(referenced) = queue.pop_front() {
+            for (i, kinds) in referrers.get(referenced).into_iter().flatten() {
+                let class = &classes[*i];
+                self.add_result(SearchResult {
+                    line_number: None,
+                    line_content: format!("{} -> {} ({})",
+                        classfile::to_binary_name(&class.name),
+                        classfile::to_binary_name(referenced),
+                        kinds.iter().copied().collect::<Vec<_>>().join(", ")),
+                    match_type: "reference".to_string(),
+                    ..SearchResult::at(&class.location)
+                });
+                if visited.insert(&class.name) {
+                    queue.push_back(&class.name);
+                }
+            }
+        }
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    pub fn run_queries(&self, queries: &[BatchQuery], search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting batch search with {} queries", queries.len()));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+        self.log_status(&format!("{} Running {} queries in a single pass", "INFO".green(), queries.len()));
+
+        // Class queries are compared case-folded; fold them once, not per entry
+        let class_queries: Vec<Cow<str>> = queries
+            .iter()
+            .map(|query| match &query.kind {
+                QueryKind::Class(q) | QueryKind::ClassContains(q) => self.fold_case(q),
+                _ => Cow::Borrowed(""),
+            })
+            .collect();
+
+        // Content queries share one pattern set, so they are matched exactly like --search
+        let (sources, labels): (Vec<String>, Vec<String>) = queries
+            .iter()
+            .filter_map(|query| match &query.kind {
+                QueryKind::Content(regex) => Some((regex.as_str().to_string(), query.label.clone())),
+                _ => None,
+            })
+            .unzip();
+        let content = if sources.is_empty() {
+            None
+        } else {
+            let mut patterns = self.compile_patterns(&sources)?;
+            patterns.labels = labels;
+            patterns.tagged = true;
+            Some(patterns)
+        };
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.run_queries_in_jar(jar_path, queries, &class_queries, content.as_ref());
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    pub fn search_method(&self, method_name: &str, descriptor: Option<&str>, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting method search for: {}", method_name));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_method_in_jar(jar_path, method_name, descriptor);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Finds classes, fields and methods carrying an annotation, matched by
+    /// fully-qualified or simple name (`RestController` matches
+    /// `org.springframework.web.bind.annotation.RestController`).
+    pub fn search_annotation(&self, annotation: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting annotation search for: {}", annotation));
+        let start_time = Instant::now();
+
+        let annotation = classfile::to_binary_name(annotation);
+        let annotation = self.fold_case(&annotation);
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_annotation_in_jar(jar_path, &annotation);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    pub fn search_manifest(&self, key: Option<&str>, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting manifest search for: {}", key.unwrap_or("all attributes")));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_manifest_in_jar(jar_path, key);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Checks the manifest `Class-Path` of every JAR: each entry is resolved
+    /// against the JAR's directory and reported as `missing_classpath_entry`
+    /// when nothing exists there. Entries with a URL scheme other than
+    /// `file:` cannot be checked and are skipped.
+    pub fn check_classpath(&self, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting manifest Class-Path check");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.check_classpath_of_jar(jar_path);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Looks up keys in `.properties` files, both on disk and inside JARs. The
+    /// pattern is a regex that must match the whole key, so a plain key such as
+    /// `spring.datasource.url` finds exactly that key.
+    pub fn search_property_key(&self, key_pattern: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting property key search for: {}", key_pattern));
+        let start_time = Instant::now();
+
+        let keys = PatternSet::new(&[format!("^(?:{})$", key_pattern)], self.ignore_case)?;
+        let files = self.find_archive_files(search_dir, &["jar", "properties"])?;
+        let jar_count = files.iter().filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar"))).count();
+        self.update_stats(|stats| stats.total_jars = jar_count);
+
+        self.log_status(&format!("{} Found {} JAR and {} properties files to process", "INFO".green(), jar_count, files.len() - jar_count));
+
+        self.for_each_with_progress(&files, |path| {
+            if !self.should_process_file(path) {
+                return;
+            }
+
+            if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar")) {
+                self.search_properties_in_jar(path, &keys);
+            } else if let Some(bytes) = self.open_file(path).ok().and_then(|mut file| {
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes).ok().map(|_| bytes)
+            }) {
+                self.search_properties_text(&bytes, &Location::file(path.display()), &keys);
+                self.update_stats(|stats| stats.total_other_files += 1);
+            }
+            self.update_stats(|stats| stats.files_processed += 1);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports each JAR's distribution of class file versions and flags classes
+    /// compiled for a newer Java release than `max_java`.
+    pub fn search_bytecode_versions(&self, max_java: Option<u16>, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting bytecode version report");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_bytecode_versions_in_jar(jar_path, max_java);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports the Maven `group:artifact:version` embedded in each JAR.
+    pub fn search_coordinates(&self, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting Maven coordinate extraction");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_coordinates_in_jar(jar_path);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports the commit, branch and build time each JAR was built from, read
+    /// from an embedded `git.properties` or else the manifest's `Build-Jdk`
+    /// and `Build-Time` attributes.
+    pub fn search_build_info(&self, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting build info extraction");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_build_info_in_jar(jar_path);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Flags JARs whose Maven coordinates match a known-bad advisory.
+    pub fn check_vulnerabilities(&self, advisories: &[Advisory], search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting vulnerability check against {} advisories", advisories.len()));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.check_vulnerabilities_in_jar(jar_path, advisories);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports each JAR as `signed_valid`, `signed_invalid` or `unsigned`.
+    pub fn verify_signatures(&self, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting JAR signature verification");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.verify_signature_of_jar(jar_path);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports classes shipped by more than one JAR, flagging whether the copies
+    /// are byte-identical or divergent (classpath conflicts).
+    pub fn find_duplicate_classes(&self, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting duplicate class detection");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let index: DigestIndex = Arc::new(Mutex::new(HashMap::new()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.collect_class_digests(jar_path, &index);
+        });
+
+        self.report_duplicates(&index, "class")?;
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports resources (any non-class entry) at the same path in more than
+    /// one JAR, such as two `logback.xml` files, flagging whether the copies
+    /// are identical or divergent. With a glob only matching paths are
+    /// considered; without one, entries that are expected in every JAR
+    /// (manifest, signatures, licenses, Maven metadata, service files) are skipped.
+    pub fn find_duplicate_resources(&self, pattern: Option<&str>, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting duplicate resource detection for: {}", pattern.unwrap_or("all resources")));
+        let start_time = Instant::now();
+        let matcher = pattern.map(|p| Glob::new(p).map(|g| g.compile_matcher())).transpose()?;
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let index: DigestIndex = Arc::new(Mutex::new(HashMap::new()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.collect_resource_digests(jar_path, matcher.as_ref(), &index);
+        });
+
+        self.report_duplicates(&index, "resource")?;
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    // One result per copy of every key shipped by more than one JAR, as
+    // `duplicate_<kind>_identical` or `duplicate_<kind>_divergent`
+    fn report_duplicates(&self, index: &DigestIndex, kind: &str) -> Result<(), JarToolError> {
+        let index = std::mem::take(&mut *index.lock().map_err(|_| JarToolError::Poisoned("digest index"))?);
+        let mut duplicates: Vec<(String, Vec<EntryCopy>)> = index
+            .into_iter()
+            .filter(|(_, copies)| {
+                let jars: HashSet<&PathBuf> = copies.iter().map(|c| &c.jar_path).collect();
+                jars.len() > 1
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, mut copies) in duplicates {
+            copies.sort_by(|a, b| a.jar_path.cmp(&b.jar_path));
+            let identical = copies.iter().all(|c| c.digest == copies[0].digest);
+            let status = if identical { "identical" } else { "divergent" };
+
+            for copy in &copies {
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: format!("{} [{} across {} JARs] sha256:{}",
+                        name, status, copies.len(), hex_digest(&copy.digest[..4])),
+                    match_type: format!("duplicate_{}_{}", kind, status),
+                    ..SearchResult::at(&Location::entry(copy.jar_path.display(), &copy.entry_name))
+                };
+                self.add_result(result);
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds classes whose superclass is `target`; with `transitive`, their
+    /// subclasses too, across every scanned JAR.
+    pub fn search_extends(&self, target: &str, transitive: bool, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        self.search_hierarchy(target, false, transitive, search_dir)
+    }
+
+    /// Finds classes that declare `target` among their interfaces; with
+    /// `transitive`, also their subclasses, sub-interfaces of `target` and
+    /// the classes implementing those.
+    pub fn search_implements(&self, target: &str, transitive: bool, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        self.search_hierarchy(target, true, transitive, search_dir)
+    }
+
+    fn search_hierarchy(&self, target: &str, implements: bool, transitive: bool, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        let relation = if implements { "implements" } else { "extends" };
+        self.log_verbose(&format!("Starting hierarchy search: {} {}", relation, target));
+        let start_time = Instant::now();
+
+        let target = classfile::to_binary_name(target);
+        let target = self.fold_case(&target);
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let nodes: Mutex<Vec<ClassNode>> = Mutex::new(Vec::new());
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.collect_class_nodes(jar_path, &nodes);
+        });
+        let nodes = nodes.into_inner().map_err(|_| JarToolError::Poisoned("class index"))?;
+
+        // Supertype name -> (subtype node, reached through `extends`?)
+        let mut subtypes: HashMap<&str, Vec<(usize, bool)>> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            if let Some(super_name) = &node.super_name {
+                subtypes.entry(super_name).or_default().push((i, true));
+            }
+            for interface in &node.interfaces {
+                subtypes.entry(interface).or_default().push((i, false));
+            }
+        }
+
+        let mut seeds: Vec<&str> = subtypes
+            .keys()
+            .copied()
+            .filter(|name| self.class_name_matches(name, &target, true))
+            .collect();
+        seeds.sort();
+
+        // Breadth-first from each matching supertype: direct hits first, then (with
+        // --transitive) anything reachable through further extends/implements edges
+        let mut found: BTreeMap<usize, (String, Option<String>)> = BTreeMap::new();
+        for seed in seeds {
+            let mut queue: VecDeque<(&str, Option<&str>)> = VecDeque::from([(seed, None)]);
+            let mut visited: HashSet<&str> = HashSet::from([seed]);
+
+            while let Some((supertype, via)) = queue.pop_front() {
+                for &(i, is_extends) in subtypes.get(supertype).into_iter().flatten() {
+                    let direct = via.is_none();
+                    // The first hop must be the requested relation; later hops may be either
+                    if direct && is_extends == implements {
+                        continue;
+                    }
+                    if !direct && !implements && !is_extends {
+                        continue;
+                    }
+
+                    let node = &nodes[i];
+                    found.entry(i).or_insert_with(|| (seed.to_string(), via.map(str::to_string)));
+                    if transitive && visited.insert(&node.name) {
+                        queue.push_back((&node.name, Some(&node.name)));
+                    }
+                }
+            }
+        }
+
+        for (i, (supertype, via)) in found {
+            let node = &nodes[i];
+            let via = via.map(|v| format!(" (via {})", v)).unwrap_or_default();
+            let result = SearchResult {
+                line_number: None,
+                line_content: format!("{} {} {}{}", node.name, relation, supertype, via),
+                match_type: relation.to_string(),
+                ..SearchResult::at(&node.location)
+            };
+            self.add_result(result);
+        }
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    pub fn search_content(&self, pattern: &str, search_dir: &Path, file_types: &[&str]) -> Result<Vec<SearchResult>, JarToolError> {
+        self.search_content_patterns(&[pattern.to_string()], search_dir, file_types)
+    }
+
+    /// Content search for several regexes in one pass over each JAR; results
+    /// are tagged with the pattern(s) that matched.
+    pub fn search_content_patterns(&self, patterns: &[String], search_dir: &Path, file_types: &[&str]) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting content search for: {}", patterns.join(", ")));
+        let start_time = Instant::now();
+
+        let patterns = self.compile_patterns(patterns)?;
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_content_in_jar(jar_path, &patterns, file_types);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Searches the blobs of a committed revision (`HEAD~3`, a tag, a commit id)
+    /// straight from the object database, without checking anything out.
+    /// Archives and class files are always searched; `all_files` adds text
+    /// blobs as in master mode. Locations read `REV:path[!entry]`.
+    pub fn search_git_revision(&self, patterns: &[String], repo_dir: &Path, rev: &str, all_files: bool) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting git revision search in {} for: {}", rev, patterns.join(", ")));
+        let start_time = Instant::now();
+
+        let patterns = self.compile_patterns(patterns)?;
+        let repo = Repository::discover(repo_dir)?;
+        let mut blobs: HashMap<PathBuf, Oid> = HashMap::new();
+        repo.revparse_single(rev)?.peel_to_tree()?.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    blobs.insert(PathBuf::from(format!("{}{}", root, name)), entry.id());
+                }
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        let mut paths: Vec<PathBuf> = blobs
+            .keys()
+            .filter(|path| !self.should_exclude_path(path))
+            .filter(|path| {
+                let name = path.to_string_lossy();
+                is_nested_archive(&name)
+                    || name.to_lowercase().ends_with(".zip")
+                    || name.ends_with(".class")
+                    || (all_files && self.should_include_path(path, Path::new("")))
+            })
+            .cloned()
+            .collect();
+        paths.sort();
+
+        self.log_status(&format!("{} Found {} blobs to process in {}", "INFO".green(), paths.len(), rev));
+
+        // The repository handle is not Sync; it is only held while a blob is read
+        let repo = Mutex::new(repo);
+        self.for_each_with_progress(&paths, |path| {
+            let content = match repo.lock() {
+                Ok(repo) => repo.find_blob(blobs[path]).map(|blob| blob.content().to_vec()),
+                Err(_) => return,
+            };
+            match content {
+                Ok(content) => self.search_git_blob(path, &content, rev, &patterns),
+                Err(e) => self.log_verbose(&format!("Failed to read blob {}:{}: {}", rev, path.display(), e)),
+            }
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    pub fn search_java_files(&self, pattern: &str, search_dir: &Path, content_search: bool) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting Java file search for: {}", pattern));
+        let start_time = Instant::now();
+
+        let patterns = if content_search {
+            self.compile_patterns(&[pattern.to_string()])?
+        } else {
+            PatternSet::new(&[format!(".*{}.*", regex::escape(pattern))], self.ignore_case)?
+        };
+
+        let java_files = self.find_archive_files(search_dir, &["java"])?;
+
+        self.update_stats(|stats| stats.total_java_files = java_files.len());
+        self.log_status(&format!("{} Found {} Java files to process", "INFO".green(), java_files.len()));
+
+        if content_search {
+            self.for_each_with_progress(&java_files, |java_path| {
+                self.search_content_in_file(java_path, &patterns);
+            });
+        } else {
+            for java_path in &java_files {
+                if let Some(filename) = java_path.file_name() {
+                    if patterns.is_match(&filename.to_string_lossy()) {
+                        let result = SearchResult {
+                            line_number: None,
+                            line_content: "Java file name match".to_string(),
+                            match_type: "java_filename".to_string(),
+                            ..SearchResult::at(&Location::file(java_path.display()))
+                        };
+                        self.add_result(result);
+                    }
+                }
+            }
+        }
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+fn search_content_in_all_files(&self, file_path: &Path, patterns: &PatternSet) {
+    if !self.should_process_file(file_path) {
+        return;
+    }
+
+    let file_ext = file_path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| "no_extension".to_string());
+
+    self.log_verbose(&format!("Processing {} file: {}", file_ext, file_path.display()));
+
+    // Try to read as text first
+    if let Ok(file) = self.open_file(file_path) {
+        let mut reader = BufReader::new(file);
+        if let Some(encoding) = reader.fill_buf().ok().and_then(detect_text_encoding) {
+            self.search_transcoded_file(reader, encoding, file_path, patterns);
+            self.update_stats(|stats| stats.files_processed += 1);
+            return;
+        }
+
+        let mut found_text_match = false;
+        let mut context = ContextTracker::new(self.context_before, self.context_after);
+        let mut bytes_read = 0;
+        
+        // First attempt: read as UTF-8 text
+        for (line_num, line_result) in reader.lines().enumerate() {
+            match line_result {
+                Ok(line) => {
+                    bytes_read += line.len() + 1;
+                    let result = self.line_selected(patterns, &line).then(|| SearchResult {
+                        line_number: Some(line_num + 1),
+                        line_content: self.result_line(&line).to_string(),
+                        match_type: self.get_file_type(file_path),
+                        query: patterns.query_for(&line),
+                        match_spans: patterns.spans(self.result_line(&line)),
+                        ..SearchResult::at(&Location::file(file_path.display()))
+                    });
+                    found_text_match |= result.is_some();
+                    context.push(&line, line_num + 1, result, &mut |r| self.add_result(r));
+                },
+                Err(_) => {
+                    // If we encounter a read error (likely binary or encoding issue), 
+                    // try binary search for remaining content (which cannot be inverted)
+                    if !found_text_match && !self.invert_match {
+                        self.log_verbose(&format!("Text read failed for {}, trying binary search", file_path.display()));
+                        self.search_binary_file(file_path, patterns);
+                    }
+                    break;
+                }
+            }
+        }
+        context.finish(&mut |r| self.add_result(r));
+        self.add_bytes_scanned(bytes_read);
+        
+        self.update_stats(|stats| stats.files_processed += 1);
+    } else {
+        self.log_verbose(&format!("Failed to open file: {}", file_path.display()));
+    }
+}
+
+
+
+fn search_binary_file(&self, file_path: &Path, patterns: &PatternSet) {
+    if let Ok(file) = self.open_file(file_path) {
+        let match_type = format!("{}_binary", self.get_file_type(file_path));
+        let scanned = for_each_binary_string(file, self.min_string_len, patterns.overlap(), |offset, string| {
+            if patterns.is_match(string) {
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: string.to_string(),
+                    match_type: match_type.clone(),
+                    query: patterns.query_for(string),
+                    match_spans: patterns.spans(string),
+                    byte_offset: Some(offset),
+                    ..SearchResult::at(&Location::file(file_path.display()))
+                };
+                self.add_result(result);
+            }
+        });
+        match scanned {
+            Ok(bytes) => self.add_bytes_scanned(bytes),
+            Err(e) => self.log_verbose(&format!("Binary read failed for {}: {}", file_path.display(), e)),
+        }
+    }
+}
+
+pub fn master_search(&self, pattern: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+    self.master_search_patterns(&[pattern.to_string()], search_dir)
+}
+
+/// Shows what a master search would scan: the per-category counts after
+/// --exclude/--include (and, with --verbose, every file), without opening
+/// anything.
+pub fn master_dry_run(&self, search_dir: &Path) -> Result<(), JarToolError> {
+    let files = self.categorize_master_files(search_dir);
+
+    self.log_status(&format!("{} Dry run: nothing will be opened or searched", "INFO".blue()));
+    for (label, bucket) in files.buckets() {
+        println!("  {}: {}", label, bucket.len());
+        if self.verbose {
+            for path in bucket {
+                println!("    {}", path.display());
+            }
+        }
+    }
+    println!("  {} Total files to process: {}", "TOTAL".cyan(), files.total());
+
+    Ok(())
+}
+
+// Walks search_dir with the exclusion/include filters and sorts the files into
+// the buckets master mode searches in separate phases
+fn categorize_master_files(&self, search_dir: &Path) -> MasterFiles {
+    // Find all types of files with exclusion filtering
+    let all_files: Vec<PathBuf> = self.walk_files(search_dir)
+        .into_iter()
+        // Archives are always opened; --include is applied to their entries instead
+        .filter(|path| {
+            path.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy();
+                is_nested_archive(&name) || is_tar_archive(&name)
+            }) || self.should_include_path(path, search_dir)
+        })
+        .collect();
+
+    let mut files = MasterFiles::default();
+
+    // Categorize files by type for better reporting
+    for file in all_files {
+        // `.tar.gz` has to be recognised before its `.gz` extension is
+        if file.file_name().is_some_and(|name| is_tar_archive(&name.to_string_lossy())) {
+            files.tar_files.push(file);
+        } else if let Some(ext) = file.extension() {
+            match ext.to_str() {
+                Some("jar") => files.jar_files.push(file),
+                Some("zip") | Some("war") | Some("ear") => files.zip_files.push(file),
+                Some("java") => files.java_files.push(file),
+                Some("properties") | Some("conf") | Some("config") | Some("cfg") | Some("ini") => files.config_files.push(file),
+                Some("bat") | Some("cmd") | Some("sh") | Some("ps1") | Some("py") | Some("rb") => files.script_files.push(file),
+                Some("xml") | Some("xsd") | Some("xsl") | Some("xslt") => files.xml_files.push(file),
+                Some("txt") | Some("md") | Some("log") | Some("yaml") | Some("yml") | Some("json") => files.text_files.push(file),
+                _ => files.unknown_files.push(file),
+            }
+        } else {
+            // Process files without extensions too
+            files.unknown_files.push(file);
+        }
+    }
+
+    files
+}
+
+/// Master search for several regexes, walking the tree and opening each file once.
+pub fn master_search_patterns(&self, patterns: &[String], search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+    let first_result = self.result_count();
+    self.log_status(&format!("{} Starting master search mode for: {}", "MASTER".yellow().bold(), patterns.join(", ")));
+    if self.mini_mode {
+        self.log_status(&format!("{} Mini mode: showing unique files only", "MODE".purple()));
+    }
+    self.log_status(&format!("{} Processing ALL file types (.properties, .bat, .conf, .xml, etc.)", "INFO".green()));
+    
+    let start_time = Instant::now();
+    let patterns = self.compile_patterns(patterns)?;
+
+    let files = self.categorize_master_files(search_dir);
+    let MasterFiles { jar_files, zip_files, tar_files, java_files, .. } = &files;
+    let all_other_files = files.other_files();
+    self.record_phase("discovery", start_time);
+
+    self.update_stats(|stats| {
+        stats.total_jars = jar_files.len();
+        stats.total_zip_files = zip_files.len();
+        stats.total_tar_files = tar_files.len();
+        stats.total_java_files = java_files.len();
+        stats.total_other_files = all_other_files.len();
+    });
+
+    self.log_status(&format!("{} File analysis:", "INFO".green()));
+    for (label, bucket) in files.buckets() {
+        self.log_status(&format!("  {}: {}", label, bucket.len()));
+    }
+    self.log_status(&format!("  {} Total files to process: {}", "TOTAL".cyan(), files.total()));
+
+    // Search in JAR files
+    if !jar_files.is_empty() {
+        self.log_status(&format!("{} Searching in JAR files...", "PHASE".cyan()));
+        let phase_start = Instant::now();
+        self.for_each_with_progress(jar_files, |jar_path| {
+            self.search_content_in_jar(jar_path, &patterns, &["*"]);
+        });
+        self.record_phase("jar", phase_start);
+    }
+
+    // Search in ZIP files
+    if !zip_files.is_empty() {
+        self.log_status(&format!("{} Searching in ZIP files...", "PHASE".cyan()));
+        let phase_start = Instant::now();
+        self.for_each_with_progress(zip_files, |zip_path| {
+            self.search_content_in_zip(zip_path, &patterns);
+        });
+        self.record_phase("zip", phase_start);
+    }
+
+    // Search in TAR archives
+    if !tar_files.is_empty() {
+        self.log_status(&format!("{} Searching in TAR archives...", "PHASE".cyan()));
+        let phase_start = Instant::now();
+        self.for_each_with_progress(tar_files, |tar_path| {
+            self.search_content_in_tar(tar_path, &patterns);
+        });
+        self.record_phase("tar", phase_start);
+    }
+
+    // Search in Java files
+    if !java_files.is_empty() {
+        self.log_status(&format!("{} Searching in Java files...", "PHASE".cyan()));
+        let phase_start = Instant::now();
+        self.for_each_with_progress(java_files, |java_path| {
+            self.search_content_in_file(java_path, &patterns);
+        });
+        self.record_phase("java", phase_start);
+    }
+
+    // Search in ALL other files (config, scripts, XML, text, etc.)
+    if !all_other_files.is_empty() {
+        self.log_status(&format!("{} Searching in configuration, script, and other files (.properties, .bat, .conf, .xml, etc.)...", "PHASE".cyan()));
+        let phase_start = Instant::now();
+        self.for_each_with_progress(&all_other_files, |file_path| {
+            self.search_content_in_all_files(file_path, &patterns);
+        });
+        self.record_phase("other", phase_start);
+    }
+
+    self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+    self.log_status(&format!("{} Master search completed!", "SUCCESS".green()));
+    Ok(self.results_since(first_result))
+}
+
+    /// Rewrites every match of `patterns` in the text files under `search_dir`
+    /// with `replacement` (which may refer to capture groups as `$1` or
+    /// `${name}`), keeping the original as `<file>.bak` when `backup` is set.
+    /// Each rewritten file is reported once, with its replacement count.
+    /// Archives are never modified: their entries are read-only.
+    pub fn replace_in_files(&self, patterns: &[String], replacement: &str, search_dir: &Path, backup: bool) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_status(&format!("{} Replacing matches of {} with \"{}\"", "REPLACE".yellow().bold(), patterns.join(", "), replacement));
+        let start_time = Instant::now();
+        let patterns = self.compile_patterns(patterns)?;
+
+        let files = self.categorize_master_files(search_dir);
+        let archives = files.jar_files.len() + files.zip_files.len() + files.tar_files.len();
+        if archives > 0 {
+            eprintln!("{} Not rewriting {} archives: entries inside JAR, ZIP and TAR files are read-only", "WARNING".yellow(), archives);
+        }
+
+        // Backups and temp files left by an earlier run would otherwise be rewritten in turn
+        let text_files: Vec<PathBuf> = files
+            .java_files
+            .iter()
+            .cloned()
+            .chain(files.other_files())
+            .filter(|path| path.extension().is_none_or(|ext| ext != "bak"))
+            .filter(|path| !path.to_string_lossy().ends_with(REPLACE_TEMP_SUFFIX))
+            .collect();
+        self.update_stats(|stats| {
+            stats.total_java_files = files.java_files.len();
+            stats.total_other_files = text_files.len() - files.java_files.len();
+        });
+        self.log_status(&format!("{} Found {} text files to process", "INFO".green(), text_files.len()));
+
+        self.for_each_with_progress(&text_files, |path| {
+            self.replace_in_file(path, &patterns, replacement, backup);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    fn replace_in_file(&self, path: &Path, patterns: &PatternSet, replacement: &str, backup: bool) {
+        if !self.should_process_file(path) {
+            return;
+        }
+
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => return self.record_failure(path, e),
+        };
+        self.add_bytes_scanned(bytes.len());
+        self.update_stats(|stats| stats.files_processed += 1);
+
+        // Only UTF-8 is written back; anything else (binaries included) is left alone
+        let Ok(original) = String::from_utf8(bytes) else {
+            self.log_verbose(&format!("Not rewriting {}: not UTF-8 text", path.display()));
+            return;
+        };
+        if !patterns.is_match(&original) {
+            return;
+        }
+
+        // Line by line, as the search matched, so `^` and `$` keep their meaning;
+        // line endings are carried over untouched
+        let mut text = String::with_capacity(original.len());
+        let mut replacements = 0;
+        for raw_line in original.split_inclusive('\n') {
+            let line = raw_line.trim_end_matches(['\n', '\r']);
+            let mut rewritten = Cow::Borrowed(line);
+            for regex in &patterns.regexes {
+                let count = regex.find_iter(&rewritten).count();
+                if count > 0 {
+                    replacements += count;
+                    rewritten = Cow::Owned(regex.replace_all(&rewritten, replacement).into_owned());
+                }
+            }
+            text.push_str(&rewritten);
+            text.push_str(&raw_line[line.len()..]);
+        }
+        if text == original {
+            return;
+        }
+
+        if backup {
+            let mut backup_path = path.as_os_str().to_owned();
+            backup_path.push(".bak");
+            if let Err(e) = std::fs::write(&backup_path, &original) {
+                return self.record_failure(path, format!("cannot write backup: {}", e));
+            }
+        }
+        // Written aside and renamed over the original, so an interrupted run never
+        // leaves half a file behind, backup or not
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(REPLACE_TEMP_SUFFIX);
+        let written = std::fs::write(&temp_path, &text)
+            .and_then(|()| std::fs::metadata(path))
+            .and_then(|metadata| std::fs::set_permissions(&temp_path, metadata.permissions()))
+            .and_then(|()| std::fs::rename(&temp_path, path));
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp_path);
+            return self.record_failure(path, e);
+        }
+
+        self.log_verbose(&format!("Rewrote {} ({} replacements)", path.display(), replacements));
+        self.add_result(SearchResult {
+            line_number: None,
+            line_content: format!("{} replacement{}", replacements, if replacements == 1 { "" } else { "s" }),
+            match_type: "replaced".to_string(),
+            ..SearchResult::at(&Location::file(path.display()))
+        });
+    }
+
+    /// Finds classes whose fully-qualified name matches a regex (unanchored, so
+    /// use `^`/`$` to pin it, e.g. `Test$` or `^com\.acme\.(foo|bar)\.`).
+    pub fn search_class_regex(&self, pattern: &str, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting class regex search for: {}", pattern));
+        let start_time = Instant::now();
+        let regex = self.compile_patterns(&[pattern.to_string()])?;
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_class_in_jar(jar_path, |class_name| regex.is_match(class_name));
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+    fn search_class_in_jar<F: Fn(&str) -> bool>(&self, jar_path: &Path, matches: F) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Processing JAR: {}", jar_path.display()));
+
+        let Some(classes) = self.jar_classes(jar_path) else {
+            return;
+        };
+
+        let location = Location::file(jar_path.display());
+        for (file_name, class_name) in &classes {
+            if self.archive_done(&location) {
+                break;
+            }
+            if matches(class_name) {
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: class_name.clone(),
+                    match_type: "class".to_string(),
+                    ..SearchResult::at(&location.child(file_name))
+                };
+                self.add_result(result);
+            }
+        }
+
+        self.update_stats(|stats| {
+            stats.files_processed += 1;
+            stats.total_class_files += classes.len();
+        });
+    }
+
+    // (entry, class name) for every class in the JAR, from --cache when the
+    // JAR is unchanged since it was last read; None if it cannot be opened
+    fn jar_classes(&self, jar_path: &Path) -> Option<Vec<(String, String)>> {
+        let cached = self.class_cache.as_ref().zip(std::fs::metadata(jar_path).ok());
+        if let Some((cache, metadata)) = &cached {
+            if let Some(classes) = cache.lookup(jar_path, metadata, self.accurate_class_names) {
+                self.log_verbose(&format!("Using cached class list for {}", jar_path.display()));
+                return Some(classes);
+            }
+        }
+
+        let file = self.open_archive(jar_path).ok()?;
+        let mut archive = self.zip_archive(file, jar_path.display()).ok()?;
+        let mut classes = Vec::new();
+
+        for i in 0..archive.len() {
+            if let Ok(file_in_zip) = archive.by_index(i) {
+                let file_name = file_in_zip.name().to_string();
+
+                if file_name.ends_with(".class") {
+                    let class_name = self.resolve_class_name(file_in_zip, &file_name);
+                    classes.push((file_name, class_name));
+                }
+            }
+        }
+
+        if let Some((cache, metadata)) = &cached {
+            cache.store(jar_path, metadata, self.accurate_class_names, classes.clone());
+        }
+        Some(classes)
+    }
+
+    // A name as class_name_matches() compares it: lowercased with --ignore-case,
+    // otherwise borrowed unchanged
+    fn fold_case<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if self.ignore_case {
+            Cow::Owned(name.to_lowercase())
+        } else {
+            Cow::Borrowed(name)
+        }
+    }
+
+    // Exact matches accept the simple or fully-qualified name. `query` must
+    // already be passed through fold_case(), once per search.
+    fn class_name_matches(&self, class_name: &str, query: &str, exact_match: bool) -> bool {
+        let class_name = self.fold_case(class_name);
+
+        if exact_match {
+            class_name.strip_suffix(query).is_some_and(|rest| rest.is_empty() || rest.ends_with('.'))
+        } else if self.whole_word {
+            // With --word the substring must span whole name segments (`.`/`/` boundaries)
+            let is_boundary = |c: Option<char>| c.is_none_or(|c| c == '.' || c == '/');
+            class_name.match_indices(query).any(|(start, matched)| {
+                is_boundary(class_name[..start].chars().next_back())
+                    && is_boundary(class_name[start + matched.len()..].chars().next())
+            })
+        } else {
+            class_name.contains(query)
+        }
+    }
+
+    fn inspect_class_in_jar(&self, jar_path: &Path, query: &str) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Looking for {} in JAR: {}", query, jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+
+                for i in 0..archive.len() {
+                    if let Ok(mut file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if !file_name.ends_with(".class") {
+                            continue;
+                        }
+                        class_count += 1;
+
+                        let mut buffer = Vec::new();
+                        if file_in_zip.read_to_end(&mut buffer).is_err() {
+                            continue;
+                        }
+
+                        let class_name = self.resolve_class_name(&buffer[..], &file_name);
+                        if !self.class_name_matches(&class_name, query, true) {
+                            continue;
+                        }
+
+                        let class_file = match ClassFile::parse(&buffer[..]) {
+                            Ok(class_file) => class_file,
+                            Err(e) => {
+                                self.log_verbose(&format!("Failed to parse class {}: {}", file_name, e));
+                                continue;
+                            }
+                        };
+
+                        // One result per line of the javap-style summary
+                        let location = Location::entry(jar_path.display(), &file_name);
+                        let lines = std::iter::once(("class", class_file.declaration()))
+                            .chain(class_file.fields.iter().map(|f| ("field", class_file.field_declaration(f))))
+                            .chain(class_file.methods.iter().map(|m| ("method", class_file.method_declaration(m))));
+
+                        for (match_type, line_content) in lines {
+                            let result = SearchResult {
+                                line_number: None,
+                                line_content,
+                                match_type: match_type.to_string(),
+                                ..SearchResult::at(&location)
+                            };
+                            self.add_result(result);
+                        }
+                    }
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    // Derives the class name from the entry path, or from the constant pool's
+    // this_class when --accurate-class-names is set (multi-release/obfuscated JARs)
+    fn resolve_class_name<R: Read>(&self, reader: R, file_name: &str) -> String {
+        if self.accurate_class_names {
+            match ClassFile::parse(reader) {
+                Ok(class_file) => {
+                    if let Some(name) = class_file.this_class_name() {
+                        return classfile::to_binary_name(name);
+                    }
+                }
+                Err(e) => self.log_verbose(&format!("Falling back to path-derived name for {}: {}", file_name, e)),
+            }
+        }
+
+        file_name
+            .strip_suffix(".class")
+            .unwrap_or(file_name)
+            .replace('/', ".")
+    }
+
+    fn search_package_in_jar(&self, jar_path: &Path, package_path: &str) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name();
+                        
+                        if file_name.ends_with(".class") && file_name.starts_with(package_path) {
+                            let class_name = file_name
+                                .strip_suffix(".class")
+                                .unwrap_or(file_name)
+                                .replace('/', ".");
+                            
+                            let result = SearchResult {
+                                line_number: None,
+                                line_content: class_name,
+                                match_type: "package".to_string(),
+                                ..SearchResult::at(&Location::entry(jar_path.display(), file_name))
+                            };
+                            self.add_result(result);
+                        }
+                    }
+                }
+                self.update_stats(|stats| stats.files_processed += 1);
+            }
+        }
+    }
+
+    fn search_references_in_jar(&self, jar_path: &Path, target_internal: &str) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Scanning constant pools in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if !file_name.ends_with(".class") {
+                            continue;
+                        }
+                        class_count += 1;
+
+                        let class_file = match ClassFile::parse(file_in_zip) {
+                            Ok(class_file) => class_file,
+                            Err(e) => {
+                                self.log_verbose(&format!("Failed to parse {}!{}: {}", jar_path.display(), file_name, e));
+                                continue;
+                            }
+                        };
+
+                        let this_class = class_file.this_class_name().unwrap_or_default();
+                        if this_class == target_internal {
+                            continue;
+                        }
+
+                        // Collect distinct reference kinds, e.g. "class, methodref"
+                        let kinds: BTreeSet<&str> = class_file
+                            .referenced_classes()
+                            .into_iter()
+                            .filter(|(name, _)| name == target_internal)
+                            .map(|(_, kind)| kind)
+                            .collect();
+
+                        if !kinds.is_empty() {
+                            let result = SearchResult {
+                                line_number: None,
+                                line_content: format!("{} -> {} ({})",
+                                    classfile::to_binary_name(this_class),
+                                    classfile::to_binary_name(target_internal),
+                                    kinds.into_iter().collect::<Vec<_>>().join(", ")),
+                                match_type: "reference".to_string(),
+                                ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+                            };
+                            self.add_result(result);
+                        }
+                    }
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    /// Reports classes that use any of `symbols` (reflection APIs by default,
+    /// see [`ReflectionSymbol::defaults`]), one result per class listing the
+    /// symbols it uses.
+    pub fn search_reflection(&self, symbols: &[ReflectionSymbol], search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting reflection search for {} symbols", symbols.len()));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_reflection_in_jar(jar_path, symbols);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    fn search_reflection_in_jar(&self, jar_path: &Path, symbols: &[ReflectionSymbol]) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Scanning constant pools in JAR: {}", jar_path.display()));
+
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+        let mut class_count = 0;
+
+        for i in 0..archive.len() {
+            let Ok(file_in_zip) = archive.by_index(i) else { continue };
+            let file_name = file_in_zip.name().to_string();
+            if !file_name.ends_with(".class") {
+                continue;
+            }
+            class_count += 1;
+
+            let class_file = match ClassFile::parse(file_in_zip) {
+                Ok(class_file) => class_file,
+                Err(e) => {
+                    self.log_verbose(&format!("Failed to parse {}!{}: {}", jar_path.display(), file_name, e));
+                    continue;
+                }
+            };
+
+            let used = reflection::find_symbols(&class_file, symbols);
+            if used.is_empty() {
+                continue;
+            }
+
+            let result = SearchResult {
+                line_number: None,
+                line_content: format!("{} uses {}",
+                    classfile::to_binary_name(class_file.this_class_name().unwrap_or_default()),
+                    used.iter().map(|symbol| symbol.to_string()).collect::<Vec<_>>().join(", ")),
+                match_type: "reflection".to_string(),
+                ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+            };
+            self.add_result(result);
+        }
+
+        self.update_stats(|stats| {
+            stats.files_processed += 1;
+            stats.total_class_files += class_count;
+        });
+    }
+
+    fn search_method_in_jar(&self, jar_path: &Path, method_name: &str, descriptor: Option<&str>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Scanning method tables in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if !file_name.ends_with(".class") {
+                            continue;
+                        }
+                        class_count += 1;
+
+                        let class_file = match ClassFile::parse(file_in_zip) {
+                            Ok(class_file) => class_file,
+                            Err(e) => {
+                                self.log_verbose(&format!("Failed to parse {}!{}: {}", jar_path.display(), file_name, e));
+                                continue;
+                            }
+                        };
+
+                        let class_name = classfile::to_binary_name(class_file.this_class_name().unwrap_or_default());
+
+                        for method in &class_file.methods {
+                            let name = class_file.member_name(method).unwrap_or_default();
+                            let method_descriptor = class_file.member_descriptor(method).unwrap_or_default();
+
+                            if name != method_name || descriptor.is_some_and(|d| d != method_descriptor) {
+                                continue;
+                            }
+
+                            let result = SearchResult {
+                                line_number: None,
+                                line_content: format!("{}#{}{}", class_name, name, method_descriptor),
+                                match_type: "method".to_string(),
+                                ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+                            };
+                            self.add_result(result);
+                        }
+                    }
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    fn search_annotation_in_jar(&self, jar_path: &Path, annotation: &str) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Scanning annotations in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if !file_name.ends_with(".class") {
+                            continue;
+                        }
+                        class_count += 1;
+
+                        let class_file = match ClassFile::parse(file_in_zip) {
+                            Ok(class_file) => class_file,
+                            Err(e) => {
+                                self.log_verbose(&format!("Failed to parse {}!{}: {}", jar_path.display(), file_name, e));
+                                continue;
+                            }
+                        };
+
+                        let class_name = classfile::to_binary_name(class_file.this_class_name().unwrap_or_default());
+
+                        // (annotated element, its attributes): the class, then its fields and methods
+                        let mut targets = vec![(class_name.clone(), &class_file.attributes)];
+                        for field in &class_file.fields {
+                            let name = class_file.member_name(field).unwrap_or_default();
+                            targets.push((format!("{}#{}", class_name, name), &field.attributes));
+                        }
+                        for method in &class_file.methods {
+                            let name = class_file.member_name(method).unwrap_or_default();
+                            let descriptor = class_file.member_descriptor(method).unwrap_or_default();
+                            targets.push((format!("{}#{}{}", class_name, name, descriptor), &method.attributes));
+                        }
+
+                        for (target, attributes) in targets {
+                            for annotation_type in class_file.annotation_types(attributes) {
+                                if !self.class_name_matches(&annotation_type, annotation, true) {
+                                    continue;
+                                }
+
+                                let result = SearchResult {
+                                    line_number: None,
+                                    line_content: format!("@{} {}", annotation_type, target),
+                                    match_type: "annotation".to_string(),
+                                    ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+                                };
+                                self.add_result(result);
+                            }
+                        }
+                    }
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    fn search_manifest_in_jar(&self, jar_path: &Path, key: Option<&str>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                match Manifest::read_from_archive(&mut archive) {
+                    Some(manifest) => {
+                        let attributes: Vec<(&str, &str)> = manifest.main_attributes.iter()
+                            .filter(|(k, _)| key.is_none_or(|key| k.eq_ignore_ascii_case(key)))
+                            .map(|(k, v)| (k.as_str(), v.as_str()))
+                            .collect();
+
+                        for (k, v) in attributes {
+                            let result = SearchResult {
+                                line_number: None,
+                                line_content: format!("{}: {}", k, v),
+                                match_type: "manifest".to_string(),
+                                ..SearchResult::at(&Location::entry(jar_path.display(), manifest::MANIFEST_PATH))
+                            };
+                            self.add_result(result);
+                        }
+                    }
+                    None => self.log_verbose(&format!("No manifest in {}", jar_path.display())),
+                }
+                self.update_stats(|stats| stats.files_processed += 1);
+            }
+        }
+    }
+
+    fn check_classpath_of_jar(&self, jar_path: &Path) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+        self.update_stats(|stats| stats.files_processed += 1);
+        let Some(manifest) = Manifest::read_from_archive(&mut archive) else {
+            self.log_verbose(&format!("No manifest in {}", jar_path.display()));
+            return;
+        };
+
+        let base = jar_path.parent().unwrap_or(Path::new(""));
+        for entry in manifest.class_path() {
+            let path = match entry.split_once(':') {
+                Some(("file", path)) => path,
+                // A drive letter (`C:/lib/a.jar`) is a path, anything longer a URL scheme
+                Some((scheme, _)) if scheme.len() > 1 => {
+                    self.log_verbose(&format!("Not checking {} in {}: not a file URL", entry, jar_path.display()));
+                    continue;
+                }
+                _ => entry.as_str(),
+            };
+
+            let target = base.join(path);
+            if target.exists() {
+                continue;
+            }
+            self.add_result(SearchResult {
+                line_number: None,
+                line_content: format!("Class-Path entry {} not found (expected at {})", entry, target.display()),
+                match_type: "missing_classpath_entry".to_string(),
+                ..SearchResult::at(&Location::entry(jar_path.display(), manifest::MANIFEST_PATH))
+            });
+        }
+    }
+
+    /// Lists ServiceLoader registrations (`META-INF/services/<interface>`) as
+    /// `interface -> impl`, for every interface or just `interface` (matched
+    /// like --class, so a simple name works).
+    pub fn search_services(&self, interface: Option<&str>, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose(&format!("Starting service provider search for: {}", interface.unwrap_or("all services")));
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let interface = interface.map(|interface| self.fold_case(interface));
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_services_in_jar(jar_path, interface.as_deref());
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    fn search_services_in_jar(&self, jar_path: &Path, interface: Option<&str>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Reading service registrations in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                for i in 0..archive.len() {
+                    let Ok(mut file_in_zip) = archive.by_index(i) else { continue };
+                    let file_name = file_in_zip.name().to_string();
+                    let Some(service) = file_name.strip_prefix(SERVICES_DIR).filter(|s| !s.is_empty() && !s.contains('/')) else {
+                        continue;
+                    };
+                    if interface.is_some_and(|interface| !self.class_name_matches(service, interface, true)) {
+                        continue;
+                    }
+
+                    let mut text = String::new();
+                    if file_in_zip.read_to_string(&mut text).is_err() {
+                        self.log_verbose(&format!("Cannot read {}!{}", jar_path.display(), file_name));
+                        continue;
+                    }
+
+                    // One provider class per line; `#` starts a comment
+                    for (line_num, line) in text.lines().enumerate() {
+                        let provider = line.split('#').next().unwrap_or_default().trim();
+                        if provider.is_empty() {
+                            continue;
+                        }
+                        self.add_result(SearchResult {
+                            line_number: Some(line_num + 1),
+                            line_content: format!("{} -> {}", service, provider),
+                            match_type: "service_provider".to_string(),
+                            ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+                        });
+                    }
+                }
+                self.update_stats(|stats| stats.files_processed += 1);
+            }
+        }
+    }
+
+    /// License inventory: every LICENSE/NOTICE/COPYING entry of each JAR, with
+    /// its SPDX identifier when one can be told, else its first line. JARs
+    /// shipping none are reported too.
+    pub fn search_licenses(&self, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting license inventory");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_licenses_in_jar(jar_path);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    fn search_licenses_in_jar(&self, jar_path: &Path) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Reading license files in JAR: {}", jar_path.display()));
+
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+
+        let mut found = false;
+        for i in 0..archive.len() {
+            let Ok(mut file_in_zip) = archive.by_index(i) else { continue };
+            let file_name = file_in_zip.name().to_string();
+            if file_in_zip.is_dir() || !is_license_entry(&file_name) {
+                continue;
+            }
+            found = true;
+
+            let mut bytes = Vec::new();
+            if file_in_zip.read_to_end(&mut bytes).is_err() {
+                self.log_verbose(&format!("Cannot read {}!{}", jar_path.display(), file_name));
+                continue;
+            }
+            let text = decode_text(&bytes).unwrap_or_else(|| String::from_utf8_lossy(&bytes));
+
+            let is_notice = file_name.rsplit('/').next().is_some_and(|name| name.to_uppercase().starts_with("NOTICE"));
+            let line_content = match identify_license(&text) {
+                Some(id) => id,
+                None => {
+                    let first_line = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+                    if is_notice {
+                        first_line.to_string()
+                    } else {
+                        format!("(unidentified) {}", first_line)
+                    }
+                }
+            };
+
+            self.add_result(SearchResult {
+                line_content,
+                match_type: if is_notice { "license_notice" } else { "license" }.to_string(),
+                ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+            });
+        }
+
+        if !found {
+            self.add_result(SearchResult {
+                line_content: "no LICENSE or NOTICE entries".to_string(),
+                match_type: "license_missing".to_string(),
+                ..SearchResult::at(&Location::file(jar_path.display()))
+            });
+        }
+        self.update_stats(|stats| stats.files_processed += 1);
+    }
+
+    fn search_properties_in_jar(&self, jar_path: &Path, keys: &PatternSet) {
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+
+        for i in 0..archive.len() {
+            let Ok(mut file_in_zip) = archive.by_index(i) else { continue };
+            let file_name = file_in_zip.name().to_string();
+            if !file_name.ends_with(".properties") {
+                continue;
+            }
+
+            let mut bytes = Vec::new();
+            if file_in_zip.read_to_end(&mut bytes).is_ok() {
+                self.search_properties_text(&bytes, &Location::entry(jar_path.display(), &file_name), keys);
+                self.update_stats(|stats| stats.total_other_files += 1);
+            }
+        }
+    }
+
+    fn search_properties_text(&self, bytes: &[u8], location: &Location, keys: &PatternSet) {
+        let text = decode_text(bytes).unwrap_or_else(|| String::from_utf8_lossy(bytes));
+
+        for property in properties::parse(&text) {
+            if keys.is_match(&property.key) {
+                let result = SearchResult {
+                    line_number: Some(property.line),
+                    line_content: format!("{}={}", property.key, property.value),
+                    match_type: "property".to_string(),
+                    ..SearchResult::at(location)
+                };
+                self.add_result(result);
+            }
+        }
+    }
+
+    fn search_bytecode_versions_in_jar(&self, jar_path: &Path, max_java: Option<u16>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Reading class versions in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+                let mut versions: BTreeMap<u16, usize> = BTreeMap::new();
+
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if !file_name.ends_with(".class") {
+                            continue;
+                        }
+                        class_count += 1;
+
+                        let major = match ClassFile::read_version(file_in_zip) {
+                            Ok((major, _)) => major,
+                            Err(e) => {
+                                self.log_verbose(&format!("Unreadable class header {}: {}", file_name, e));
+                                continue;
+                            }
+                        };
+                        *versions.entry(major).or_default() += 1;
+
+                        let release = classfile::java_release(major);
+                        let too_new = max_java.is_some_and(|max| release > max);
+                        // Multi-release variants for newer runtimes are ignored by older ones
+                        let versioned_for = file_name
+                            .strip_prefix("META-INF/versions/")
+                            .and_then(|rest| rest.split('/').next())
+                            .and_then(|v| v.parse::<u16>().ok());
+                        let skipped_by_runtime = versioned_for.zip(max_java).is_some_and(|(v, max)| v > max);
+
+                        if too_new && !skipped_by_runtime {
+                            let result = SearchResult {
+                                line_number: None,
+                                line_content: format!("{} ({}) exceeds Java {}",
+                                    major, classfile::java_release_name(major), max_java.unwrap_or_default()),
+                                match_type: "bytecode_too_new".to_string(),
+                                ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+                            };
+                            self.add_result(result);
+                        }
+                    }
+                }
+
+                if !versions.is_empty() {
+                    let distribution: Vec<String> = versions
+                        .iter()
+                        .map(|(major, count)| format!("{} ({}): {} classes", major, classfile::java_release_name(*major), count))
+                        .collect();
+                    let result = SearchResult {
+                        line_number: None,
+                        line_content: distribution.join(", "),
+                        match_type: "bytecode_version".to_string(),
+                        ..SearchResult::at(&Location::file(jar_path.display()))
+                    };
+                    self.add_result(result);
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    fn search_coordinates_in_jar(&self, jar_path: &Path) {
+        for coordinate in self.read_jar_coordinates(jar_path) {
+            let result = SearchResult {
+                line_number: None,
+                line_content: coordinate.to_string(),
+                match_type: "maven_coordinate".to_string(),
+                ..SearchResult::at(&Location::file(jar_path.display()))
+            };
+            self.add_result(result);
+        }
+    }
+
+    fn search_build_info_in_jar(&self, jar_path: &Path) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+        self.update_stats(|stats| stats.files_processed += 1);
+
+        match BuildInfo::read_from_archive(&mut archive) {
+            Some(info) => self.add_result(SearchResult {
+                line_number: None,
+                line_content: info.to_string(),
+                match_type: "build_info".to_string(),
+                ..SearchResult::at(&Location::entry(jar_path.display(), &info.source))
+            }),
+            None => self.log_verbose(&format!("No build info in {}", jar_path.display())),
+        }
+    }
+
+    fn check_vulnerabilities_in_jar(&self, jar_path: &Path, advisories: &[Advisory]) {
+        for coordinate in self.read_jar_coordinates(jar_path) {
+            for advisory in advisories.iter().filter(|a| a.matches(&coordinate)) {
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: format!("{} matches {}", coordinate, advisory),
+                    match_type: "vulnerable_dependency".to_string(),
+                    ..SearchResult::at(&Location::file(jar_path.display()))
+                };
+                self.add_result(result);
+            }
+        }
+    }
+
+    fn read_jar_coordinates(&self, jar_path: &Path) -> Vec<MavenCoordinate> {
+        if !self.should_process_file(jar_path) {
+            return Vec::new();
+        }
+
+        let mut coordinates = Vec::new();
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                coordinates = MavenCoordinate::read_from_archive(&mut archive);
+                if coordinates.is_empty() {
+                    self.log_verbose(&format!("No Maven metadata in {}", jar_path.display()));
+                }
+                self.update_stats(|stats| stats.files_processed += 1);
+            }
+        }
+        coordinates
+    }
+
+    fn verify_signature_of_jar(&self, jar_path: &Path) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Verifying signature digests in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let (match_type, line_content) = match signing::verify_archive(&mut archive) {
+                    SignatureStatus::Unsigned => ("unsigned", "No signature files".to_string()),
+                    SignatureStatus::Valid(signers) => ("signed_valid", format!("Signed by {}", signers.join(", "))),
+                    SignatureStatus::Invalid(reason) => ("signed_invalid", reason),
+                };
+
+                let result = SearchResult {
+                    line_number: None,
+                    line_content,
+                    match_type: match_type.to_string(),
+                    ..SearchResult::at(&Location::file(jar_path.display()))
+                };
+                self.add_result(result);
+                self.update_stats(|stats| stats.files_processed += 1);
+            }
+        }
+    }
+
+    /// Checks files against a coreutils-style checksum list, resolving relative
+    /// paths against `search_dir`. Every listed file is reported as
+    /// `checksum_ok`, `checksum_mismatch` or `checksum_missing`.
+    pub fn verify_checksums(&self, expected: &[ExpectedChecksum], search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting checksum verification");
+        let start_time = Instant::now();
+
+        self.log_status(&format!("{} Verifying {} checksums", "INFO".green(), expected.len()));
+
+        self.for_each_with_progress(expected, |entry| {
+            let path = search_dir.join(&entry.path);
+            let algorithm = entry.algorithm.name();
+
+            let (match_type, line_content) = if !path.is_file() {
+                ("checksum_missing", format!("{} listed but not found", entry.path.display()))
+            } else {
+                match entry.algorithm.digest_file(&path) {
+                    Ok(actual) if actual == entry.digest => ("checksum_ok", format!("{}:{}", algorithm, actual)),
+                    Ok(actual) => ("checksum_mismatch", format!("expected {}:{}, got {}:{}", algorithm, entry.digest, algorithm, actual)),
+                    Err(e) => {
+                        self.record_failure(&path, e);
+                        return;
+                    }
+                }
+            };
+
+            self.add_result(SearchResult {
+                line_number: None,
+                line_content,
+                match_type: match_type.to_string(),
+                ..SearchResult::at(&Location::file(path.display()))
+            });
+            self.update_stats(|stats| stats.files_processed += 1);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports packages with classes in more than one JAR ("split packages"),
+    /// which the module system rejects. One result per package and JAR.
+    pub fn find_split_packages(&self, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting split package detection");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let index: PackageIndex = Arc::new(Mutex::new(HashMap::new()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.collect_packages(jar_path, &index);
+        });
+
+        let index = std::mem::take(&mut *index.lock().map_err(|_| JarToolError::Poisoned("package index"))?);
+        let mut split: Vec<(String, HashSet<PathBuf>)> = index.into_iter().filter(|(_, jars)| jars.len() > 1).collect();
+        split.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (package, jars) in split {
+            let mut jars: Vec<PathBuf> = jars.into_iter().collect();
+            jars.sort();
+
+            for jar_path in &jars {
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: format!("{} [split across {} JARs]", package, jars.len()),
+                    match_type: "split_package".to_string(),
+                    ..SearchResult::at(&Location::file(jar_path.display()))
+                };
+                self.add_result(result);
+            }
+        }
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    /// Reports each JAR's module descriptor: the module name, then one result
+    /// per `requires`, `exports` and `opens` directive. With a filter, only the
+    /// matching `requires` or `exports` directives are reported. JARs without
+    /// `module-info.class` are noted with their automatic module name.
+    pub fn search_module_info(&self, filter: Option<&ModuleFilter>, search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting module descriptor search");
+        let start_time = Instant::now();
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            self.search_module_info_in_jar(jar_path, filter);
+        });
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    fn search_module_info_in_jar(&self, jar_path: &Path, filter: Option<&ModuleFilter>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Reading module descriptor in JAR: {}", jar_path.display()));
+
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+        self.update_stats(|stats| stats.files_processed += 1);
+
+        // Multi-release JARs may carry the descriptor only under META-INF/versions/N
+        let entry_name = archive
+            .file_names()
+            .filter(|name| *name == "module-info.class" || (name.starts_with("META-INF/versions/") && name.ends_with("/module-info.class")))
+            .min_by_key(|name| name.len())
+            .map(str::to_string);
+
+        let Some(entry_name) = entry_name else {
+            if filter.is_some() {
+                self.log_verbose(&format!("No module-info.class in {}", jar_path.display()));
+                return;
+            }
+
+            let declared = Manifest::read_from_archive(&mut archive)
+                .and_then(|manifest| manifest.get("Automatic-Module-Name").map(str::to_string));
+            let (location, content) = match declared {
+                Some(name) => (
+                    Location::entry(jar_path.display(), manifest::MANIFEST_PATH),
+                    format!("{} (automatic module, from Automatic-Module-Name)", name),
+                ),
+                None => {
+                    let file_name = jar_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    (Location::file(jar_path.display()), format!("{} (automatic module, derived from the file name)", automatic_module_name(&file_name)))
+                }
+            };
+            self.add_result(SearchResult {
+                line_number: None,
+                line_content: content,
+                match_type: "automatic_module".to_string(),
+                ..SearchResult::at(&location)
+            });
+            return;
+        };
+
+        let location = Location::entry(jar_path.display(), &entry_name);
+        let descriptor = match archive.by_name(&entry_name).map_err(|e| e.to_string()).and_then(|entry| ClassFile::parse(entry).map_err(|e| e.to_string())) {
+            Ok(class_file) => class_file.module_descriptor(),
+            Err(e) => {
+                self.record_failure(location.to_string(), e);
+                return;
+            }
+        };
+        let Some(module) = descriptor else {
+            self.record_failure(location.to_string(), "no Module attribute");
+            return;
+        };
+        self.update_stats(|stats| stats.total_class_files += 1);
+
+        let report = |match_type: &str, content: String| {
+            self.add_result(SearchResult {
+                line_number: None,
+                line_content: content,
+                match_type: match_type.to_string(),
+                ..SearchResult::at(&location)
+            });
+        };
+        let qualified = |targets: &[String]| match targets.is_empty() {
+            true => String::new(),
+            false => format!(" to {}", targets.join(", ")),
+        };
+
+        if filter.is_none() {
+            let version = module.version.as_deref().map(|v| format!("@{}", v)).unwrap_or_default();
+            let open = if module.open { "open " } else { "" };
+            report("module", format!("{}module {}{}", open, module.name, version));
+        }
+
+        for requires in &module.requires {
+            if filter.is_some_and(|filter| !matches!(filter, ModuleFilter::Requires(m) if *m == requires.module)) {
+                continue;
+            }
+            let modifiers = match (requires.transitive, requires.static_phase) {
+                (true, true) => "transitive static ",
+                (true, false) => "transitive ",
+                (false, true) => "static ",
+                (false, false) => "",
+            };
+            report("module_requires", format!("{} requires {}{}", module.name, modifiers, requires.module));
+        }
+
+        for exports in &module.exports {
+            if filter.is_some_and(|filter| !matches!(filter, ModuleFilter::Exports(p) if *p == exports.package)) {
+                continue;
+            }
+            report("module_exports", format!("{} exports {}{}", module.name, exports.package, qualified(&exports.targets)));
+        }
+
+        if filter.is_none() {
+            for opens in &module.opens {
+                report("module_opens", format!("{} opens {}{}", module.name, opens.package, qualified(&opens.targets)));
+            }
+        }
+    }
+
+    /// Flags relocated (shaded) copies of libraries: classes whose package
+    /// contains a relocation prefix, one result per JAR and relocated root,
+    /// grouped by root. Each notes how many JARs also ship the original,
+    /// unrelocated packages, the usual "two Guavas, one shaded" conflict.
+    pub fn search_shaded(&self, extra_prefixes: &[String], search_dir: &Path) -> Result<Vec<SearchResult>, JarToolError> {
+        let first_result = self.result_count();
+        self.log_verbose("Starting shaded package detection");
+        let start_time = Instant::now();
+
+        let mut prefixes: Vec<Vec<String>> = DEFAULT_SHADE_PREFIXES
+            .iter()
+            .map(|prefix| prefix.to_string())
+            .chain(extra_prefixes.iter().cloned())
+            .map(|prefix| prefix.trim_matches('.').split('.').map(str::to_string).collect())
+            .collect();
+        // Where two prefixes start at the same segment the longer one wins
+        prefixes.sort_by_key(|prefix| std::cmp::Reverse(prefix.len()));
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        self.update_stats(|stats| stats.total_jars = jar_files.len());
+
+        self.log_status(&format!("{} Found {} JAR files to process", "INFO".green(), jar_files.len()));
+
+        let jars: Mutex<Vec<ShadedJar>> = Mutex::new(Vec::new());
+
+        self.for_each_with_progress(&jar_files, |jar_path| {
+            if let Some(jar) = self.collect_shaded_packages(jar_path, &prefixes) {
+                if let Ok(mut jars) = jars.lock() {
+                    jars.push(jar);
+                }
+            }
+        });
+
+        let mut jars = jars.into_inner().map_err(|_| JarToolError::Poisoned("shaded package"))?;
+        jars.sort_by(|a, b| a.jar_path.cmp(&b.jar_path));
+
+        let mut groups: BTreeMap<&str, Vec<(&ShadedJar, &str, usize)>> = BTreeMap::new();
+        for jar in &jars {
+            for ((root, original), count) in &jar.relocated {
+                groups.entry(root).or_default().push((jar, original, *count));
+            }
+        }
+
+        for (root, members) in groups {
+            for (jar, original, count) in members {
+                let original_prefix = format!("{}.", original);
+                let unrelocated = jars
+                    .iter()
+                    .filter(|other| other.plain_packages.iter().any(|p| p == original || p.starts_with(&original_prefix)))
+                    .count();
+                let note = match unrelocated {
+                    0 => String::new(),
+                    n => format!(", also unrelocated in {} JAR{}", n, if n == 1 { "" } else { "s" }),
+                };
+
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: format!("{} [relocated {}, {} class{}{}]", root, original, count, if count == 1 { "" } else { "es" }, note),
+                    match_type: "shaded_package".to_string(),
+                    ..SearchResult::at(&Location::file(jar.jar_path.display()))
+                };
+                self.add_result(result);
+            }
+        }
+
+        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
+        Ok(self.results_since(first_result))
+    }
+
+    fn collect_shaded_packages(&self, jar_path: &Path, prefixes: &[Vec<String>]) -> Option<ShadedJar> {
+        if !self.should_process_file(jar_path) {
+            return None;
+        }
+
+        self.log_verbose(&format!("Reading packages in JAR: {}", jar_path.display()));
+
+        let classes = self.jar_classes(jar_path)?;
+        let mut jar = ShadedJar { jar_path: jar_path.to_path_buf(), ..Default::default() };
+
+        for (file_name, class_name) in &classes {
+            if file_name.starts_with("META-INF/") || file_name.ends_with("module-info.class") {
+                continue;
+            }
+            let Some((package, _)) = class_name.rsplit_once('.') else { continue };
+
+            match relocation_root(package, prefixes) {
+                Some(root) => *jar.relocated.entry(root).or_default() += 1,
+                None => {
+                    jar.plain_packages.insert(package.to_string());
+                }
+            }
+        }
+
+        self.update_stats(|stats| {
+            stats.files_processed += 1;
+            stats.total_class_files += classes.len();
+        });
+        Some(jar)
+    }
+
+    fn collect_packages(&self, jar_path: &Path, index: &PackageIndex) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Reading packages in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+                let mut packages = HashSet::new();
+
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        // Multi-release variants live under META-INF/versions and repeat
+                        // packages of the same JAR; module-info belongs to no package
+                        if !file_name.ends_with(".class") || file_name.starts_with("META-INF/") || file_name.ends_with("module-info.class") {
+                            continue;
+                        }
+                        class_count += 1;
+
+                        let class_name = self.resolve_class_name(file_in_zip, &file_name);
+                        // The unnamed package cannot be exported, so it never splits a module
+                        if let Some((package, _)) = class_name.rsplit_once('.') {
+                            packages.insert(package.to_string());
+                        }
+                    }
+                }
+
+                // One lock per JAR rather than per class
+                if let Ok(mut index) = index.lock() {
+                    for package in packages {
+                        index.entry(package).or_default().insert(jar_path.to_path_buf());
+                    }
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    fn collect_resource_digests(&self, jar_path: &Path, matcher: Option<&GlobMatcher>, index: &DigestIndex) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Hashing resources in JAR: {}", jar_path.display()));
+
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+        let mut resource_count = 0;
+        let mut copies = Vec::new();
+
+        for i in 0..archive.len() {
+            let Ok(mut file_in_zip) = archive.by_index(i) else { continue };
+            let file_name = file_in_zip.name().to_string();
+
+            if file_name.ends_with('/') || file_name.ends_with(".class") {
+                continue;
+            }
+            let wanted = match matcher {
+                Some(matcher) => matcher.is_match(&file_name),
+                None => !is_per_jar_metadata(&file_name),
+            };
+            if !wanted {
+                continue;
+            }
+            resource_count += 1;
+
+            let mut buffer = Vec::new();
+            if file_in_zip.read_to_end(&mut buffer).is_err() {
+                continue;
+            }
+            copies.push((file_name.clone(), EntryCopy {
+                jar_path: jar_path.to_path_buf(),
+                entry_name: file_name,
+                digest: Sha256::digest(&buffer).into(),
+            }));
+        }
+
+        if let Ok(mut index) = index.lock() {
+            for (path, copy) in copies {
+                index.entry(path).or_default().push(copy);
+            }
+        }
+
+        self.update_stats(|stats| {
+            stats.files_processed += 1;
+            stats.total_other_files += resource_count;
+        });
+    }
+
+    fn collect_class_digests(&self, jar_path: &Path, index: &DigestIndex) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Hashing classes in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+                let mut copies = Vec::new();
+
+                for i in 0..archive.len() {
+                    if let Ok(mut file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if !file_name.ends_with(".class") {
+                            continue;
+                        }
+                        class_count += 1;
+
+                        let mut buffer = Vec::new();
+                        if file_in_zip.read_to_end(&mut buffer).is_err() {
+                            continue;
+                        }
+
+                        let class_name = self.resolve_class_name(&buffer[..], &file_name);
+                        copies.push((class_name, EntryCopy {
+                            jar_path: jar_path.to_path_buf(),
+                            entry_name: file_name,
+                            digest: Sha256::digest(&buffer).into(),
+                        }));
+                    }
+                }
+
+                // One lock per JAR rather than per class
+                if let Ok(mut index) = index.lock() {
+                    for (class_name, copy) in copies {
+                        index.entry(class_name).or_default().push(copy);
+                    }
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    fn collect_class_refs(&self, jar_path: &Path, classes: &Mutex<Vec<ClassRefs>>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Scanning constant pools in JAR: {}", jar_path.display()));
+
+        let Ok(file) = self.open_archive(jar_path) else { return };
+        let Ok(mut archive) = self.zip_archive(file, jar_path.display()) else { return };
+
+        let mut jar_classes = Vec::new();
+        for i in 0..archive.len() {
+            let Ok(file_in_zip) = archive.by_index(i) else { continue };
+            let file_name = file_in_zip.name().to_string();
+            if !file_name.ends_with(".class") {
+                continue;
+            }
+
+            match ClassFile::parse(file_in_zip) {
+                Ok(class_file) => jar_classes.push(ClassRefs {
+                    name: class_file.this_class_name().unwrap_or_default().to_string(),
+                    location: Location::entry(jar_path.display(), &file_name),
+                    refs: class_file.referenced_classes(),
+                }),
+                Err(e) => self.log_verbose(&format!("Failed to parse {}!{}: {}", jar_path.display(), file_name, e)),
+            }
+        }
+
+        let class_count = jar_classes.len();
+        // One lock per JAR rather than per class
+        if let Ok(mut classes) = classes.lock() {
+            classes.extend(jar_classes);
+        }
+
+        self.update_stats(|stats| {
+            stats.files_processed += 1;
+            stats.total_class_files += class_count;
+        });
+    }
+
+    fn collect_class_nodes(&self, jar_path: &Path, nodes: &Mutex<Vec<ClassNode>>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Reading class hierarchy in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut jar_nodes = Vec::new();
+
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if !file_name.ends_with(".class") {
+                            continue;
+                        }
+
+                        let class_file = match ClassFile::parse(file_in_zip) {
+                            Ok(class_file) => class_file,
+                            Err(e) => {
+                                self.log_verbose(&format!("Failed to parse {}!{}: {}", jar_path.display(), file_name, e));
+                                continue;
+                            }
+                        };
+
+                        jar_nodes.push(ClassNode {
+                            name: classfile::to_binary_name(class_file.this_class_name().unwrap_or_default()),
+                            location: Location::entry(jar_path.display(), &file_name),
+                            super_name: class_file.super_class_name().map(classfile::to_binary_name),
+                            interfaces: class_file.interface_names().into_iter().map(classfile::to_binary_name).collect(),
+                        });
+                    }
+                }
+
+                let class_count = jar_nodes.len();
+                // One lock per JAR rather than per class
+                if let Ok(mut nodes) = nodes.lock() {
+                    nodes.extend(jar_nodes);
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    fn run_queries_in_jar(&self, jar_path: &Path, queries: &[BatchQuery], class_queries: &[Cow<str>], content: Option<&PatternSet>) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Running batch queries in JAR: {}", jar_path.display()));
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                let mut class_count = 0;
+
+                for i in 0..archive.len() {
+                    if let Ok(mut file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name().to_string();
+
+                        if file_name.ends_with('/') {
+                            continue;
+                        }
+
+                        let is_class = file_name.ends_with(".class");
+                        let class_name = if is_class {
+                            class_count += 1;
+                            file_name.strip_suffix(".class").unwrap_or(&file_name).replace('/', ".")
+                        } else {
+                            String::new()
+                        };
+
+                        for (query, class_query) in queries.iter().zip(class_queries) {
+                            let (matched, content, match_type) = match &query.kind {
+                                QueryKind::Class(_) => (is_class && self.class_name_matches(&class_name, class_query, true), &class_name, "class"),
+                                QueryKind::ClassContains(_) => (is_class && self.class_name_matches(&class_name, class_query, false), &class_name, "class"),
+                                QueryKind::Package(path) => (is_class && file_name.starts_with(path.as_str()), &class_name, "package"),
+                                QueryKind::EntryName(glob) => (glob.is_match(&file_name), &file_name, "entry_name"),
+                                QueryKind::Content(_) => continue,
+                            };
+
+                            if matched {
+                                self.add_result(SearchResult {
+                                    line_number: None,
+                                    line_content: content.clone(),
+                                    match_type: match_type.to_string(),
+                                    query: Some(query.label.clone()),
+                                    ..SearchResult::at(&Location::entry(jar_path.display(), &file_name))
+                                });
+                            }
+                        }
+
+                        // Only inflate entries when at least one query needs their contents
+                        if let Some(patterns) = content {
+                            self.search_entry_content(&mut file_in_zip, patterns, &Location::entry(jar_path.display(), &file_name), &file_name);
+                        }
+                    }
+                }
+
+                self.update_stats(|stats| {
+                    stats.files_processed += 1;
+                    stats.total_class_files += class_count;
+                });
+            }
+        }
+    }
+
+    fn search_content_in_jar(&self, jar_path: &Path, patterns: &PatternSet, file_types: &[&str]) {
+        if !self.should_process_file(jar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Searching content in JAR: {}", jar_path.display()));
+
+        let Ok(source) = self.open_archive(jar_path) else { return };
+        let location = Location::file(jar_path.display());
+        let Ok(archive) = self.zip_archive(source, &location) else { return };
+
+        // Every worker gets its own archive handle (cheap: the central directory is
+        // shared, and a clone opens its own file or reuses the mapping), so the
+        // entries of one large JAR are searched concurrently
+        let counts = (0..archive.len())
+            .into_par_iter()
+            .map_init(
+                || archive.clone(),
+                |archive, i| {
+                    self.with_entry(archive, i, &location, |file_in_zip| self.search_archive_entry(file_in_zip, &location, patterns, file_types, 0))
+                        .unwrap_or((0, 0, 0))
+                },
+            )
+            .reduce(|| (0, 0, 0), |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2));
+
+        self.update_stats(|stats| {
+            stats.files_processed += 1;
+            stats.total_class_files += counts.0;
+            stats.total_java_files += counts.1;
+            stats.total_other_files += counts.2;
+        });
+    }
+
+    // A git blob is searched like an archive entry: archives are opened in
+    // memory, class files scanned as bytecode, anything else as text
+    fn search_git_blob(&self, path: &Path, content: &[u8], rev: &str, patterns: &PatternSet) {
+        let name = path.to_string_lossy();
+        let location = Location::file(format!("{}:{}", rev, name));
+
+        if is_nested_archive(&name) || name.to_lowercase().ends_with(".zip") {
+            match self.zip_archive(self.timed(Cursor::new(content), &location), &location) {
+                Ok(mut archive) => {
+                    let counts = self.search_archive_entries(&mut archive, &location, patterns, &["*"], 0);
+                    let is_jar = name.to_lowercase().ends_with(".jar");
+                    self.update_stats(|stats| {
+                        if is_jar {
+                            stats.total_jars += 1;
+                        } else {
+                            stats.total_zip_files += 1;
+                        }
+                        stats.total_class_files += counts.0;
+                        stats.total_java_files += counts.1;
+                        stats.total_other_files += counts.2;
+                    });
+                }
+                Err(e) => self.log_verbose(&format!("Failed to open archive {}: {}", location, e)),
+            }
+        } else if name.ends_with(".class") {
+            let mut reader = content;
+            if self.string_constants_only {
+                self.search_in_string_constants(&mut reader, patterns, &location);
+            } else {
+                self.search_in_binary_content(&mut reader, patterns, &location);
+            }
+            self.update_stats(|stats| stats.total_class_files += 1);
+        } else if looks_like_text(content) {
+            self.search_in_text_content(&mut &content[..], patterns, &location, &name);
+            self.update_stats(|stats| {
+                if name.ends_with(".java") {
+                    stats.total_java_files += 1;
+                } else {
+                    stats.total_other_files += 1;
+                }
+            });
+        }
+
+        self.update_stats(|stats| stats.files_processed += 1);
+    }
+
+    // Walks every entry of an (possibly nested) archive and returns (classes, java, others)
+    fn search_archive_entries<R: Read + Seek>(&self, archive: &mut ZipArchive<R>, location: &Location, patterns: &PatternSet, file_types: &[&str], depth: usize) -> (usize, usize, usize) {
+        let mut counts = (0, 0, 0); // (classes, java, others)
+
+        for i in 0..archive.len() {
+            if let Some(entry) = self.with_entry(archive, i, location, |file_in_zip| self.search_archive_entry(file_in_zip, location, patterns, file_types, depth)) {
+                counts = (counts.0 + entry.0, counts.1 + entry.1, counts.2 + entry.2);
+            }
+        }
+
+        counts
+    }
+
+    // Searches one archive entry; returns its contribution to (classes, java, others)
+    fn search_archive_entry(&self, file_in_zip: &mut ZipFile, location: &Location, patterns: &PatternSet, file_types: &[&str], depth: usize) -> (usize, usize, usize) {
+        let file_name = file_in_zip.name().to_string();
+
+        // Skip directories
+        if file_name.ends_with('/') || self.archive_done(location) {
+            return (0, 0, 0);
+        }
+
+        let entry_location = location.child(&file_name);
+
+        // Count file types
+        let counts = if file_name.ends_with(".class") {
+            (1, 0, 0)
+        } else if file_name.ends_with(".java") {
+            (0, 1, 0)
+        } else {
+            (0, 0, 1)
+        };
+
+        if is_nested_archive(&file_name) && depth < self.max_nesting {
+            self.search_nested_archive(file_in_zip, &entry_location, patterns, file_types, depth + 1);
+            return counts;
+        }
+
+        if !self.should_include_entry(&file_name) {
+            return counts;
+        }
+
+        // Check if we should search this file type
+        let should_search = file_types.contains(&"*") || 
+            (file_types.contains(&"class") && file_name.ends_with(".class")) ||
+            (file_types.contains(&"java") && file_name.ends_with(".java")) ||
+            (file_types.contains(&"other") && !file_name.ends_with(".class") && !file_name.ends_with(".java"));
+
+        if should_search {
+            self.search_entry_content(file_in_zip, patterns, &entry_location, &file_name);
+        }
+
+        counts
+    }
+
+    // Searches the contents of one archive entry, whichever mode asked for it
+    fn search_entry_content<R: Read>(&self, reader: &mut R, patterns: &PatternSet, entry_location: &Location, file_name: &str) {
+        if file_name.ends_with(".class") && self.string_constants_only {
+            self.search_in_string_constants(reader, patterns, entry_location);
+        } else if file_name.ends_with(".class") {
+            // For class files, use strings-like extraction for bytecode
+            self.search_in_binary_content(reader, patterns, entry_location);
+        } else {
+            // For text files, search line by line
+            self.search_in_text_content(reader, patterns, entry_location, file_name);
+        }
+    }
+
+    // Inflates an archive stored inside another archive and searches it in memory
+    fn search_nested_archive<R: Read>(&self, reader: &mut R, location: &Location, patterns: &PatternSet, file_types: &[&str], depth: usize) {
+        self.log_verbose(&format!("Descending into nested archive (depth {}): {}", depth, location));
+
+        let mut buffer = Vec::new();
+        if reader.read_to_end(&mut buffer).is_err() {
+            return;
+        }
+
+        match self.zip_archive(Cursor::new(buffer), location) {
+            Ok(mut archive) => {
+                let counts = self.search_archive_entries(&mut archive, location, patterns, file_types, depth);
+                let is_jar = location.entry.to_lowercase().ends_with(".jar");
+
+                self.update_stats(|stats| {
+                    if is_jar {
+                        stats.total_jars += 1;
+                    } else {
+                        stats.total_zip_files += 1;
+                    }
+                    stats.total_class_files += counts.0;
+                    stats.total_java_files += counts.1;
+                    stats.total_other_files += counts.2;
+                });
+            }
+            Err(e) => self.log_verbose(&format!("Failed to open nested archive {}: {}", location, e)),
+        }
+    }
+
+    // Tarballs are read front to back (no random access), so each entry is
+    // searched as it streams past; JARs inside are inflated and recursed into
+    fn search_content_in_tar(&self, tar_path: &Path, patterns: &PatternSet) {
+        if !self.should_process_file(tar_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Searching content in TAR: {}", tar_path.display()));
+
+        let Ok(file) = self.open_file(tar_path) else { return };
+        let lower = tar_path.to_string_lossy().to_lowercase();
+        let reader: Box<dyn Read> = if lower.ends_with(".tgz") || lower.ends_with(".gz") {
+            Box::new(GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+
+        let location = Location::file(tar_path.display());
+        let mut archive = tar::Archive::new(reader);
+        let entries = match archive.entries() {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.log_verbose(&format!("Failed to read TAR {}: {}", location, e));
+                return;
+            }
+        };
+
+        let (mut class_count, mut java_count, mut other_count) = (0, 0, 0);
+        for entry in entries {
+            if self.archive_done(&location) {
+                break;
+            }
+            let mut entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.log_verbose(&format!("Stopped reading TAR {}: {}", location, e));
+                    break;
+                }
+            };
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let Ok(path) = entry.path() else { continue };
+            // `tar -C dir .` prefixes every member with `./`
+            let file_name = path.to_string_lossy();
+            let file_name = file_name.strip_prefix("./").unwrap_or(&file_name).to_string();
+            let entry_location = location.child(&file_name);
+
+            if file_name.ends_with(".class") {
+                class_count += 1;
+            } else if file_name.ends_with(".java") {
+                java_count += 1;
+            } else {
+                other_count += 1;
+            }
+
+            if (is_nested_archive(&file_name) || file_name.to_lowercase().ends_with(".zip")) && self.max_nesting > 0 {
+                self.search_nested_archive(&mut entry, &entry_location, patterns, &["*"], 1);
+            } else if self.should_include_entry(&file_name) {
+                if file_name.ends_with(".class") && self.string_constants_only {
+                    self.search_in_string_constants(&mut entry, patterns, &entry_location);
+                } else if file_name.ends_with(".class") {
+                    self.search_in_binary_content(&mut entry, patterns, &entry_location);
+                } else {
+                    self.search_in_text_content(&mut entry, patterns, &entry_location, &file_name);
+                }
+            }
+        }
+
+        self.update_stats(|stats| {
+            stats.files_processed += 1;
+            stats.total_class_files += class_count;
+            stats.total_java_files += java_count;
+            stats.total_other_files += other_count;
+        });
+    }
+
+    fn search_content_in_zip(&self, zip_path: &Path, patterns: &PatternSet) {
+        if !self.should_process_file(zip_path) {
+            return;
+        }
+
+        self.log_verbose(&format!("Searching content in ZIP: {}", zip_path.display()));
+
+        if let Ok(file) = self.open_archive(zip_path) {
+            if let Ok(mut archive) = self.zip_archive(file, zip_path.display()) {
+                let location = Location::file(zip_path.display());
+
+                for i in 0..archive.len() {
+                    if self.archive_done(&location) {
+                        break;
+                    }
+                    self.with_entry(&mut archive, i, &location, |file_in_zip| {
+                        let file_name = file_in_zip.name().to_string();
+                        
+                        if file_name.ends_with('/') {
+                            return;
+                        }
+
+                        let entry_location = location.child(&file_name);
+
+                        // WAR/EAR bundles carry their libraries as inner JARs
+                        if is_nested_archive(&file_name) && self.max_nesting > 0 {
+                            self.search_nested_archive(file_in_zip, &entry_location, patterns, &["*"], 1);
+                        } else if self.should_include_entry(&file_name) {
+                            self.search_in_text_content(file_in_zip, patterns, &entry_location, &file_name);
+                        }
+                    });
+                }
+                self.update_stats(|stats| stats.files_processed += 1);
+            }
+        }
+    }
+
+    fn search_content_in_file(&self, file_path: &Path, patterns: &PatternSet) {
+        if !self.should_process_file(file_path) {
+            return;
+        }
+
+        if let Ok(file) = self.open_file(file_path) {
+            let mut reader = BufReader::new(file);
+            if let Some(encoding) = reader.fill_buf().ok().and_then(detect_text_encoding) {
+                self.search_transcoded_file(reader, encoding, file_path, patterns);
+                self.update_stats(|stats| stats.files_processed += 1);
+                return;
+            }
+
+            let mut context = ContextTracker::new(self.context_before, self.context_after);
+            let mut bytes_read = 0;
+            
+            for (line_num, line_result) in reader.lines().enumerate() {
+                if let Ok(line) = line_result {
+                    bytes_read += line.len() + 1;
+                    let result = self.line_selected(patterns, &line).then(|| SearchResult {
+                        line_number: Some(line_num + 1),
+                        line_content: self.result_line(&line).to_string(),
+                        match_type: self.get_file_type(file_path),
+                        query: patterns.query_for(&line),
+                        match_spans: patterns.spans(self.result_line(&line)),
+                        ..SearchResult::at(&Location::file(file_path.display()))
+                    });
+                    context.push(&line, line_num + 1, result, &mut |r| self.add_result(r));
+                }
+            }
+            context.finish(&mut |r| self.add_result(r));
+            self.add_bytes_scanned(bytes_read);
+            self.update_stats(|stats| stats.files_processed += 1);
+        }
+    }
+
+    // Slow path for UTF-16 and BOM-prefixed files: transcode the whole file first
+    fn search_transcoded_file<R: Read>(&self, mut reader: R, encoding: &'static Encoding, file_path: &Path, patterns: &PatternSet) {
+        self.log_verbose(&format!("Decoding {} as {}", file_path.display(), encoding.name()));
+
+        let mut bytes = Vec::new();
+        if let Err(e) = reader.read_to_end(&mut bytes) {
+            self.log_verbose(&format!("Read failed for {}: {}", file_path.display(), e));
+            return;
+        }
+
+        self.add_bytes_scanned(bytes.len());
+        let (text, _, _) = encoding.decode(&bytes);
+        self.search_text_lines(&text, patterns, &Location::file(file_path.display()), &self.get_file_type(file_path));
+    }
+
+    fn search_in_text_content<R: Read>(&self, reader: &mut R, patterns: &PatternSet, file_location: &Location, file_name: &str) {
+        let mut bytes = Vec::new();
+        if reader.read_to_end(&mut bytes).is_ok() {
+            self.add_bytes_scanned(bytes.len());
+            if let Some(text) = decode_text(&bytes) {
+                self.search_text_lines(&text, patterns, file_location, &self.get_archive_file_type(file_name));
+            }
+        }
+    }
+
+    fn search_text_lines(&self, text: &str, patterns: &PatternSet, file_location: &Location, match_type: &str) {
+        let mut context = ContextTracker::new(self.context_before, self.context_after);
+        for (line_num, line) in text.lines().enumerate() {
+            let result = self.line_selected(patterns, line).then(|| SearchResult {
+                line_number: Some(line_num + 1),
+                line_content: self.result_line(line).to_string(),
+                match_type: match_type.to_string(),
+                query: patterns.query_for(line),
+                match_spans: patterns.spans(self.result_line(line)),
+                ..SearchResult::at(file_location)
+            });
+            context.push(line, line_num + 1, result, &mut |r| self.add_result(r));
+        }
+        context.finish(&mut |r| self.add_result(r));
+    }
+
+    fn search_in_binary_content<R: Read>(&self, reader: &mut R, patterns: &PatternSet, file_location: &Location) {
+        let scanned = for_each_binary_string(reader, self.min_string_len, patterns.overlap(), |offset, string| {
+            if patterns.is_match(string) {
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: string.to_string(),
+                    match_type: "class_bytecode".to_string(),
+                    query: patterns.query_for(string),
+                    match_spans: patterns.spans(string),
+                    byte_offset: Some(offset),
+                    ..SearchResult::at(file_location)
+                };
+                self.add_result(result);
+            }
+        });
+        match scanned {
+            Ok(bytes) => self.add_bytes_scanned(bytes),
+            Err(e) => self.log_verbose(&format!("Binary read failed for {}: {}", file_location, e)),
+        }
+    }
+
+    fn search_in_string_constants<R: Read>(&self, reader: &mut R, patterns: &PatternSet, file_location: &Location) {
+        let class = match ClassFile::parse(reader) {
+            Ok(class) => class,
+            Err(e) => {
+                self.log_verbose(&format!("Failed to parse class file {}: {}", file_location, e));
+                return;
+            }
+        };
+
+        for string in class.string_constants() {
+            if patterns.is_match(string) {
+                let result = SearchResult {
+                    line_number: None,
+                    line_content: string.to_string(),
+                    match_type: "class_string_constant".to_string(),
+                    query: patterns.query_for(string),
+                    match_spans: patterns.spans(string),
+                    ..SearchResult::at(file_location)
+                };
+                self.add_result(result);
+            }
+        }
+    }
+
+    // Enumerates regular files under search_dir (or the --stdin file list),
+    // applying --exclude and the traversal options (gitignore handling)
+    fn walk_files(&self, search_dir: &Path) -> Vec<PathBuf> {
+        let files: Vec<PathBuf> = match &self.file_list {
+            Some(list) => list
+                .iter()
+                .flat_map(|path| if path.is_dir() { self.walk_dir(path) } else { vec![path.clone()] })
+                .collect(),
+            None => self.walk_dir(search_dir),
+        };
+
+        files.into_iter()
+            .filter(|path| !self.should_exclude_path(path)) // Add exclusion filter
+            .collect()
+    }
+
+    fn walk_dir(&self, search_dir: &Path) -> Vec<PathBuf> {
+        // Unreadable entries and symlink cycles (reported by both walkers when
+        // following links) are skipped rather than aborting the walk
+        let log_skip = |err: &dyn std::fmt::Display| self.log_verbose(&format!("Skipping during traversal: {}", err));
+
+        let files: Vec<PathBuf> = if self.respect_gitignore {
+            WalkBuilder::new(search_dir)
+                .hidden(!self.include_hidden)
+                .max_depth(self.max_depth)
+                .require_git(false)
+                // A root given explicitly is always searched: only ignore files at or
+                // below it apply, never a parent's that may list the root itself
+                .parents(false)
+                .follow_links(self.follow_symlinks)
+                // Git never tracks its own metadata directory
+                .filter_entry(|e| e.file_name() != ".git")
+                .build()
+                .filter_map(|e| e.map_err(|err| log_skip(&err)).ok())
+                .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+                .map(|e| e.into_path())
+                .collect()
+        } else {
+            let walk = WalkDir::new(search_dir).follow_links(self.follow_symlinks);
+            match self.max_depth {
+                Some(depth) => walk.max_depth(depth),
+                None => walk,
+            }
+                .into_iter()
+                .filter_entry(|e| self.include_hidden || e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+                .filter_map(|e| e.map_err(|err| log_skip(&err)).ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+                .collect()
+        };
+
+        if !self.follow_symlinks {
+            return files;
+        }
+
+        // Links can make one file reachable under several paths; scan it once
+        let mut visited = HashSet::new();
+        files
+            .into_iter()
+            .filter(|path| {
+                let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+                let first_visit = visited.insert(canonical);
+                if !first_visit {
+                    self.log_verbose(&format!("Skipping {}: already reached through another link", path.display()));
+                }
+                first_visit
+            })
+            .collect()
+    }
+
+    fn find_archive_files(&self, search_dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, JarToolError> {
+        let files: Vec<PathBuf> = self.walk_files(search_dir)
+            .into_iter()
+            .filter(|path| {
+                if let Some(ext) = path.extension() {
+                    extensions.iter().any(|&target_ext| {
+                        ext.to_string_lossy().to_lowercase() == target_ext.to_lowercase()
+                    })
+                } else {
+                    false
+                }
+            })
+            .collect();
+
+        Ok(files)
+    }
+
+    fn should_process_file(&self, file_path: &Path) -> bool {
+        self.log_verbose(&format!("The size threshold is set to {} bytes", self.size_threshold));
+        
+        // Check exclusions first
+        if self.should_exclude_path(file_path) {
+            return false;
+        }
+
+        if let Ok(metadata) = file_path.metadata() {
+            if self.size_threshold == 0 {
+                self.log_verbose(&format!("Processing file without size threshold: {}", file_path.display()));
+            } else if metadata.len() < self.size_threshold {
+                self.log_verbose(&format!("Skipping small file: {} ({} bytes)", 
+                    file_path.display(), metadata.len()));
+                return false;
+            }
+
+            if !self.modified_in_window(&metadata) {
+                self.log_verbose(&format!("Skipping file modified outside the time window: {}", file_path.display()));
+                return false;
+            }
+        }
+
+        if let Ok(mut processed) = self.processed_files.lock() {
+            processed.insert(file_path.display().to_string());
+        }
+        true
+    }
+
+    // Files whose modification time cannot be read are left out once a window is set
+    fn modified_in_window(&self, metadata: &std::fs::Metadata) -> bool {
+        if self.modified_after.is_none() && self.modified_before.is_none() {
+            return true;
+        }
+        match metadata.modified() {
+            Ok(modified) => {
+                self.modified_after.is_none_or(|after| modified >= after)
+                    && self.modified_before.is_none_or(|before| modified < before)
+            }
+            Err(_) => false,
+        }
+    }
+
+fn get_file_type(&self, file_path: &Path) -> String {
+    if let Some(ext) = file_path.extension() {
+        let ext_str = ext.to_string_lossy().to_lowercase();
+        if let Some(label) = self.type_map.get(&ext_str) {
+            return label.clone();
+        }
+        match ext_str.as_str() {
+            "properties" => "properties_config".to_string(),
+            "conf" | "config" | "cfg" => "configuration".to_string(),
+            "bat" | "cmd" => "batch_script".to_string(),
+            "sh" => "shell_script".to_string(),
+            "xml" | "xsd" | "xsl" | "xslt" => "xml_document".to_string(),
+            "json" => "json_data".to_string(),
+            "yaml" | "yml" => "yaml_data".to_string(),
+            "ini" => "ini_config".to_string(),
+            "log" => "log_file".to_string(),
+            "txt" => "text_file".to_string(),
+            "md" => "markdown".to_string(),
+            "py" => "python_script".to_string(),
+            "rb" => "ruby_script".to_string(),
+            "ps1" => "powershell_script".to_string(),
+            _ => ext_str,
+        }
+    } else {
+        "no_extension".to_string()
+    }
+}
+
+    fn get_archive_file_type(&self, file_name: &str) -> String {
+        if let Some(ext) = file_name.split('.').next_back() {
+            self.type_map.get(&ext.to_lowercase()).cloned().unwrap_or_else(|| ext.to_string())
+        } else {
+            "unknown".to_string()
+        }
+    }
+
+    pub fn export_csv(&self, filename: &str) -> Result<(), JarToolError> {
+        let mut writer = Writer::from_writer(create_output(filename)?);
+        writer.write_record(["file_location", "archive_path", "entry_path", "line", "byte_offset", "line_content", "match_type", "query"])?;
+
+        if let Ok(results) = self.results.lock() {
+            // Workers append in whatever order they finish; rows are sorted so
+            // that repeated runs write identical files, unless --sort chose an order
+            let mut rows: Vec<&SearchResult> = results.iter().collect();
+            if !self.results_sorted.load(Ordering::Relaxed) {
+                rows.sort_by(|a, b| by_location(a, b));
+            }
+
+            for result in rows {
+                let cell = |value: &str| if self.csv_sanitize { sanitize_csv_field(value) } else { value.to_string() };
+                writer.write_record([
+                    cell(&result.file_location),
+                    cell(result.archive_path.as_deref().unwrap_or_default()),
+                    cell(&result.entry_path),
+                    result.line_number.map_or(String::new(), |n| n.to_string()),
+                    result.byte_offset.map_or(String::new(), |n| n.to_string()),
+                    cell(&self.truncate_line(&result.line_content).0),
+                    cell(&result.match_type),
+                    cell(result.query.as_deref().unwrap_or_default()),
+                ])?;
+            }
+        }
+
+        writer.flush()?;
+        if filename != "-" {
+            self.log_status(&format!("{} Results exported to {}", "SUCCESS".green(), filename));
+        }
+        Ok(())
+    }
+
+    pub fn export_json(&self, filename: &str) -> Result<(), JarToolError> {
+        let mut output = create_output(filename)?;
+
+        {
+            let results = self.results.lock().map_err(|_| JarToolError::Poisoned("results"))?;
+            let stats = self.stats.lock().map_err(|_| JarToolError::Poisoned("stats"))?;
+            let report = JsonReport {
+                results: &self.truncated_results(&results),
+                stats: StatsReport::new(&stats, self.parallel_jobs),
+                failures: self
+                    .failures()
+                    .into_iter()
+                    .map(|(path, error)| FailedFile { path: path.display().to_string(), error })
+                    .collect(),
+            };
+            serde_json::to_writer_pretty(&mut output, &report)?;
+        }
+
+        writeln!(output)?;
+        output.flush()?;
+        if filename != "-" {
+            self.log_status(&format!("{} Results exported to {}", "SUCCESS".green(), filename));
+        }
+        Ok(())
+    }
+
+    /// Writes just the statistics, with throughput, as one JSON object, for
+    /// tracking scan performance over time.
+    pub fn export_stats_json(&self, filename: &str) -> Result<(), JarToolError> {
+        let mut output = create_output(filename)?;
+
+        {
+            let stats = self.stats.lock().map_err(|_| JarToolError::Poisoned("stats"))?;
+            serde_json::to_writer_pretty(&mut output, &StatsReport::new(&stats, self.parallel_jobs))?;
+        }
+
+        writeln!(output)?;
+        output.flush()?;
+        if filename != "-" {
+            self.log_status(&format!("{} Statistics written to {}", "SUCCESS".green(), filename));
+        }
+        Ok(())
+    }
+
+    /// Writes the results as a SARIF 2.1.0 log for code-scanning integrations.
+    pub fn export_sarif(&self, filename: &str) -> Result<(), JarToolError> {
+        let mut output = create_output(filename)?;
+
+        {
+            let results = self.results.lock().map_err(|_| JarToolError::Poisoned("results"))?;
+            report::write_sarif(&mut output, &self.truncated_results(&results))?;
+        }
+
+        output.flush()?;
+        if filename != "-" {
+            self.log_status(&format!("{} SARIF log written to {}", "SUCCESS".green(), filename));
+        }
+        Ok(())
+    }
+
+    /// Writes the `reference` results as a Graphviz digraph (see `report::write_dot`).
+    pub fn export_dot(&self, filename: &str) -> Result<(), JarToolError> {
+        let mut output = create_output(filename)?;
+
+        {
+            let results = self.results.lock().map_err(|_| JarToolError::Poisoned("results"))?;
+            report::write_dot(&mut output, &results)?;
+        }
+
+        output.flush()?;
+        if filename != "-" {
+            self.log_status(&format!("{} DOT graph written to {}", "SUCCESS".green(), filename));
+        }
+        Ok(())
+    }
+
+    /// Writes a self-contained HTML report (inline CSS/JS, no external assets).
+    pub fn export_html(&self, filename: &str) -> Result<(), JarToolError> {
+        let mut output = create_output(filename)?;
+
+        {
+            let results = self.results.lock().map_err(|_| JarToolError::Poisoned("results"))?;
+            let stats = self.stats.lock().map_err(|_| JarToolError::Poisoned("stats"))?;
+            report::write_html(&mut output, &self.truncated_results(&results), &stats, self.parallel_jobs)?;
+        }
+
+        output.flush()?;
+        if filename != "-" {
+            self.log_status(&format!("{} HTML report written to {}", "SUCCESS".green(), filename));
+        }
+        Ok(())
+    }
+
+      pub fn print_stats(&self) {
+        if let Ok(stats) = self.stats.lock() {
+            let results_count = self.results.lock().map(|r| r.len()).unwrap_or(0);
+            let unique_count = if self.mini_mode {
+                self.unique_file_count()
+            } else {
+                results_count
+            };
+
+            println!("\n{}", "═══════════════════════════════════════════════════════════════".white());
+            println!("{}", "                        SEARCH STATISTICS                        ".white());
+            println!("{}", "═══════════════════════════════════════════════════════════════".white());
+            
+            println!("{:<25} {:>10}", "JAR files scanned:".cyan(), format!("{}", stats.total_jars).white());
+            println!("{:<25} {:>10}", "ZIP files scanned:".cyan(), format!("{}", stats.total_zip_files).white());
+            println!("{:<25} {:>10}", "TAR files scanned:".cyan(), format!("{}", stats.total_tar_files).white());
+            println!("{:<25} {:>10}", "Class files found:".cyan(), format!("{}", stats.total_class_files).white());
+            println!("{:<25} {:>10}", "Java files found:".cyan(), format!("{}", stats.total_java_files).white());
+            println!("{:<25} {:>10}", "Other files found:".cyan(), format!("{}", stats.total_other_files).white());
+            println!("{:<25} {:>10}", "Total files processed:".cyan(), format!("{}", stats.files_processed).white());
+            
+            if self.mini_mode {
+                println!("{:<25} {:>10}", "Unique files w/ matches:".cyan(), format!("{}", unique_count).green());
+                println!("{:<25} {:>10}", "Total matches found:".cyan(), format!("{}", stats.matches_found).yellow());
+            } else {
+                println!("{:<25} {:>10}", "Matches found:".cyan(), format!("{}", results_count).green());
+            }
+            
+            println!("{:<25} {:>10}", "Elapsed time:".cyan(), format!("{:.2}s", stats.elapsed_time.as_secs_f64()).yellow());
+            for (phase, duration) in &stats.phase_times {
+                let share = if stats.elapsed_time.is_zero() { 0.0 } else { duration.as_secs_f64() / stats.elapsed_time.as_secs_f64() * 100.0 };
+                println!("{:<25} {:>10} {}", format!("  {} phase:", phase).cyan(), format!("{:.2}s", duration.as_secs_f64()).yellow(), format!("({:.0}%)", share).dimmed());
+            }
+            
+            if !stats.elapsed_time.is_zero() {
+                println!("{:<25} {:>10}", "Files/second:".cyan(), format!("{:.2}", stats.files_per_sec()).purple());
+                println!("{:<25} {:>10}", "Classes/second:".cyan(), format!("{:.2}", stats.classes_per_sec()).purple());
+                println!("{:<25} {:>10}", "Throughput:".cyan(), format!("{:.2} MB/s", stats.mb_per_sec()).purple());
+            }
+            
+            println!("{:<25} {:>10}", "Parallel jobs:".cyan(), format!("{}", self.parallel_jobs).white());
+            println!("{:<25} {:>10}", "Mode:".cyan(), if self.mini_mode { "Mini (unique files)".purple() } else { "Full".white() });
+            
+            if !self.excludes.is_empty() || !self.exclude_regexes.is_empty() {
+                let count = self.excludes.len() + self.exclude_regexes.len();
+                println!("{:<25} {:>10}", "Exclusions:".cyan(), format!("{}", count).red());
+                for exclude in &self.excludes {
+                    println!("  {}", exclude.red());
+                }
+                for regex in &self.exclude_regexes {
+                    println!("  {} (regex)", regex.as_str().red());
+                }
+            }
+
+            // Unreadable files would otherwise look the same as files without matches
+            let failures = self.failures();
+            if !failures.is_empty() {
+                println!("{:<25} {:>10}", "Skipped/failed files:".cyan(), format!("{}", failures.len()).red());
+                for (path, error) in &failures {
+                    println!("  {}: {}", path.display().to_string().red(), error);
+                }
+            }
+            
+            println!("{}", "═══════════════════════════════════════════════════════════════".white());
+        }
+    }
+
+
+    pub fn list_jars(&self, search_dir: &Path) -> Result<(), JarToolError> {
+        println!("{}", "JAR Analysis Report".white());
+        println!("{}", "==================".cyan());
+
+        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
+        
+        if jar_files.is_empty() {
+            println!("{} No JAR files found in {}", "ERROR".red(), search_dir.display());
+            return Ok(());
+        }
+
+        println!("{} Found {} JAR files", "INFO".blue(), jar_files.len());
+        println!();
+
+        let checksum_header = self.list_checksums.map(|algorithm| format!(" {}", algorithm.name().to_uppercase())).unwrap_or_default();
+        println!("{:<50} {:>10} {:>10} {:>10} {:>10} {:>12} {:>8}{}", 
+            "JAR File", "Classes", "Java", "Files", "Size (MB)", "Unpacked MB", "Ratio", checksum_header);
+        println!("{:<50} {:>10} {:>10} {:>10} {:>10} {:>12} {:>8}{}", 
+            "--------", "-------", "----", "-----", "---------", "-----------", "-----",
+            if checksum_header.is_empty() { "" } else { " --------" });
+
+        // Count in parallel, then print in path order so the table stays stable
+        let mut rows: Vec<(&PathBuf, u64, JarContents, Option<String>)> = self.pool.install(|| {
+            jar_files
+                .par_iter()
+                .filter_map(|jar_path| {
+                    let size = jar_path.metadata().ok()?.len();
+                    let checksum = self.list_checksums.map(|algorithm| {
+                        algorithm.digest_file(jar_path).unwrap_or_else(|e| {
+                            self.record_failure(jar_path, &e);
+                            "-".to_string()
+                        })
+                    });
+                    Some((jar_path, size, self.count_jar_contents(jar_path), checksum))
+                })
+                .collect()
+        });
+        rows.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut total_stats = (0, 0, 0, 0u64); // (classes, java, files, size)
+        let mut total_contents = JarContents::default();
+        let mut suspicious = 0;
+
+        for (jar_path, size, contents, checksum) in rows {
+            let size_mb = size as f64 / (1024.0 * 1024.0);
+
+            let jar_name = jar_path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            
+            let display_name = if jar_name.len() > 47 {
+                format!("{}...", &jar_name[..44])
+            } else {
+                jar_name.to_string()
+            };
+
+            let row = format!("{:<50} {:>10} {:>10} {:>10} {:>10.2} {:>12.2} {:>8.1}{}", 
+                display_name, contents.classes, contents.java, contents.files, size_mb,
+                contents.uncompressed as f64 / (1024.0 * 1024.0), contents.ratio(),
+                checksum.map(|digest| format!(" {}", digest)).unwrap_or_default());
+
+            // Far more compressible than real code or resources: likely a zip bomb
+            if contents.ratio() > self.bomb_ratio {
+                println!("{}", row.red());
+                suspicious += 1;
+            } else {
+                println!("{}", row);
+            }
+
+            total_stats.0 += contents.classes;
+            total_stats.1 += contents.java;
+            total_stats.2 += contents.files;
+            total_stats.3 += size;
+            total_contents.uncompressed += contents.uncompressed;
+            total_contents.compressed += contents.compressed;
+        }
+
+        println!();
+        println!("{:<50} {:>10} {:>10} {:>10} {:>10.2} {:>12.2} {:>8.1}", 
+            "TOTAL", total_stats.0, total_stats.1, total_stats.2, 
+            total_stats.3 as f64 / (1024.0 * 1024.0),
+            total_contents.uncompressed as f64 / (1024.0 * 1024.0), total_contents.ratio());
+
+        if suspicious > 0 {
+            println!();
+            println!("{} {} JAR(s) exceed a compression ratio of {} (possible zip bomb)",
+                "WARNING".red(), suspicious, self.bomb_ratio);
+        }
+
+        Ok(())
+    }
+
+    fn count_jar_contents(&self, jar_path: &Path) -> JarContents {
+        let mut contents = JarContents::default();
+
+        if let Ok(file) = self.open_archive(jar_path) {
+            if let Ok(mut archive) = self.zip_archive(file, jar_path.display()) {
+                for i in 0..archive.len() {
+                    if let Ok(file_in_zip) = archive.by_index(i) {
+                        let file_name = file_in_zip.name();
+                        
+                        if !file_name.ends_with('/') {
+                            contents.files += 1;
+                            if file_name.ends_with(".class") {
+                                contents.classes += 1;
+                            } else if file_name.ends_with(".java") {
+                                contents.java += 1;
+                            }
+                        }
+
+                        // Sizes as declared in the central directory; nothing is inflated
+                        contents.uncompressed += file_in_zip.size();
+                        contents.compressed += file_in_zip.compressed_size();
+                    }
+                }
+            }
+        }
+        
+        contents
+    }
+
+    // grep-style context block: `:` marks the matching line, `-` the lines
+    // before it and `+` those after. Lines up to `shown_through`, already
+    // printed with an earlier match in the same file, are left out.
+    fn print_context(&self, context: &[String], start: usize, match_line: usize, shown_through: usize) {
+        for (line_number, line) in unshown_context(context, start, shown_through) {
+            let (line, _) = self.truncate_line(line);
+            if line_number == match_line {
+                println!("     {:>6}: {}", line_number.to_string().yellow(), line.white().bold());
+            } else if line_number < match_line {
+                println!("     {:>6}- {}", line_number.to_string().dimmed(), line.dimmed());
+            } else {
+                println!("     {:>6}+ {}", line_number.to_string().cyan(), line.dimmed());
+            }
+        }
+    }
+
+    /// Prints a histogram of results per match type, scaled to the most
+    /// frequent one.
+    pub fn print_summary(&self) {
+        const BAR_WIDTH: usize = 40;
+
+        let counts = self.match_type_counts();
+        if counts.is_empty() {
+            println!("{} No matches found", "RESULT".yellow());
+            return;
+        }
+
+        let total: usize = counts.iter().map(|(_, count)| count).sum();
+        let max = counts[0].1;
+        let label_width = counts.iter().map(|(match_type, _)| match_type.len()).max().unwrap_or(0);
+
+        println!("\n{} {} matches by type", "SUMMARY".green().bold(), total);
+        println!("{}", "─".repeat(80).cyan());
+        for (match_type, count) in &counts {
+            // Every type present gets at least one block
+            let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max));
+            println!("  {:<width$}  {:>8}  {}", match_type.purple(), count.to_string().yellow(), bar.cyan(), width = label_width);
+        }
+    }
+
+     pub fn print_results(&self) {
+        if let Ok(results) = self.results.lock() {
+            if results.is_empty() {
+                if !self.quiet {
+                    println!("{} No matches found", "RESULT".yellow());
+                }
+                return;
+            }
+
+            if !self.quiet {
+                println!("\n{} Found {} {}", 
+                    "RESULTS".green().bold(), 
+                    if self.mini_mode { results.iter().map(|r| &r.file_location).collect::<HashSet<_>>().len() } else { results.len() },
+                    if self.mini_mode { "unique files with matches" } else { "matches" }
+                );
+                println!("{}", "─".repeat(80).cyan());
+            }
+
+            // Last line printed per file, so overlapping context is shown once
+            let mut shown: HashMap<&str, usize> = HashMap::new();
+            for (i, result) in results.iter().enumerate() {
+                let shown_through = shown.get(result.file_location.as_str()).copied().unwrap_or(0);
+                self.print_result(i + 1, result, shown_through);
+                if result.context.is_some() {
+                    shown.insert(&result.file_location, last_context_line(result).max(shown_through));
+                }
+            }
+        }
+    }
+
+    fn print_result(&self, number: usize, result: &SearchResult, shown_through: usize) {
+        // Batch runs tag each result with the query that produced it
+        let query_tag = result.query.as_ref()
+            .map(|q| format!(" [{}]", q).blue().to_string())
+            .unwrap_or_default();
+
+        if self.mini_mode {
+            // Mini mode: simple file listing
+            println!("{:>3}. {}{}", number.to_string().white(), result.file_location.green(), query_tag);
+        } else {
+            // Full mode: detailed results
+            let (line, spans) = self.truncated_line(result);
+            if let Some(line_num) = result.line_number {
+                println!("{:>3}. {} {}:{}{}", 
+                    number.to_string().white(),
+                    result.file_location.green(),
+                    "line".cyan(),
+                    line_num.to_string().yellow(),
+                    query_tag
+                );
+                println!("     {}: {}", 
+                    result.match_type.purple(),
+                    highlight(&line, &spans)
+                );
+                if let (Some(context), Some(start)) = (&result.context, result.context_start) {
+                    self.print_context(context, start, line_num, shown_through);
+                }
+            } else if let Some(offset) = result.byte_offset {
+                println!("{:>3}. {} {} {}{}",
+                    number.to_string().white(),
+                    result.file_location.green(),
+                    "offset".cyan(),
+                    format!("0x{:X}", offset).yellow(),
+                    query_tag
+                );
+                println!("     {}: {}",
+                    result.match_type.purple(),
+                    highlight(&line, &spans)
+                );
+            } else {
+                println!("{:>3}. {}{} {}: {}", 
+                    number.to_string().white(),
+                    result.file_location.green(),
+                    query_tag,
+                    result.match_type.purple(),
+                    highlight(&line, &spans)
+                );
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the system temp dir, unique to this test run
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jartool-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn quiet_tool(jobs: usize) -> JarTool {
+        JarTool::new(false, 0, Some(jobs), Vec::new(), false).unwrap().with_quiet(true)
+    }
+
+    fn line_content_cells(csv_path: &Path) -> Vec<String> {
+        let mut reader = csv::Reader::from_path(csv_path).unwrap();
+        reader.records().map(|r| r.unwrap()[5].to_string()).collect()
+    }
+
+    #[test]
+    fn binary_string_match_spans_chunk_boundary() {
+        let needle = "needle";
+        let needle_at = BINARY_CHUNK_SIZE - 3;
+        let mut entry = vec![b'a'; BINARY_CHUNK_SIZE * 2 + 100];
+        entry[needle_at..needle_at + needle.len()].copy_from_slice(needle.as_bytes());
+
+        let mut longest = 0;
+        let mut found = Vec::new();
+        let scanned = for_each_binary_string(&entry[..], 4, needle.len() - 1, |offset, string| {
+            longest = longest.max(string.len());
+            if let Some(at) = string.find(needle) {
+                found.push(offset + at);
+            }
+        })
+        .unwrap();
+
+        assert_eq!(scanned, entry.len());
+        assert!(longest <= MAX_BINARY_STRING_LEN);
+        assert_eq!(found, vec![needle_at]);
+    }
+
+    #[test]
+    fn csv_export_neutralises_formula_cells() {
+        let dir = scratch_dir("csv-sanitize");
+        let formula = "=cmd|' /C calc'!A0";
+
+        for (sanitize, expected) in [(true, format!("'{}", formula)), (false, formula.to_string())] {
+            let tool = quiet_tool(1).with_csv_sanitize(sanitize);
+            tool.add_result(SearchResult {
+                line_number: Some(1),
+                line_content: formula.to_string(),
+                match_type: "properties".to_string(),
+                ..SearchResult::at(&Location::file("evil.properties"))
+            });
+
+            let csv_path = dir.join(format!("sanitize-{}.csv", sanitize));
+            tool.export_csv(csv_path.to_str().unwrap()).unwrap();
+            assert_eq!(line_content_cells(&csv_path), vec![expected]);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn csv_export_is_identical_across_runs() {
+        let dir = scratch_dir("csv-deterministic");
+        let scan_dir = dir.join("scan");
+        std::fs::create_dir(&scan_dir).unwrap();
+        let jar_path = scan_dir.join("many.jar");
+        let mut jar = zip::ZipWriter::new(File::create(&jar_path).unwrap());
+        for i in 0..32 {
+            jar.start_file(format!("conf/part{:02}.properties", i), zip::write::FileOptions::default()).unwrap();
+            writeln!(jar, "# part {}\nvalue.{}=needle-{}\nother=needle", i, i, i).unwrap();
+        }
+        jar.finish().unwrap();
+
+        let export = |run: usize| {
+            let tool = quiet_tool(4);
+            tool.master_search("needle", &scan_dir).unwrap();
+            let csv_path = dir.join(format!("run{}.csv", run));
+            tool.export_csv(csv_path.to_str().unwrap()).unwrap();
+            std::fs::read_to_string(csv_path).unwrap()
+        };
+
+        let first = export(1);
+        assert_eq!(line_content_cells(&dir.join("run1.csv")).len(), 64);
+        assert_eq!(first, export(2));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn each_tool_runs_on_its_own_pool() {
+        let dir = scratch_dir("pools");
+        std::fs::write(dir.join("app.properties"), "greeting=hello\n").unwrap();
+
+        let tools = [(2, quiet_tool(2)), (3, quiet_tool(3))];
+        for (jobs, tool) in &tools {
+            let seen = Mutex::new(HashSet::new());
+            tool.for_each_with_progress(&(0..64).collect::<Vec<_>>(), |_| {
+                seen.lock().unwrap().insert(rayon::current_num_threads());
+            });
+            assert_eq!(seen.into_inner().unwrap(), HashSet::from([*jobs]));
+
+            assert_eq!(tool.master_search("hello", &dir).unwrap().len(), 1);
+            assert_eq!(tool.stats().matches_found, 1);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn match_spans_are_clipped_at_the_line_cut() {
+        let tool = quiet_tool(1).with_max_line_length(Some(10));
+        let line = "key=secretvalue more secret";
+        let result = SearchResult {
+            line_content: line.to_string(),
+            // "secretvalue" crosses the cut at 10, the second "secret" lies past it
+            match_spans: vec![(4, 15), (21, 27)],
+            ..SearchResult::at(&Location::file("app.properties"))
+        };
+
+        let (text, spans) = tool.truncated_line(&result);
+        assert_eq!(text, "key=secret…(+17 chars)");
+        assert_eq!(spans, vec![(4, 10)]);
+
+        let exported = tool.truncated_results(std::slice::from_ref(&result));
+        assert_eq!(exported[0].line_content, text);
+        assert_eq!(exported[0].match_spans, spans);
+    }
+
+    #[test]
+    fn replace_rewrites_files_with_and_without_backup() {
+        let dir = scratch_dir("replace");
+        let original = "db.host=old-db\r\n# old-db stays in comments too\nport=5432\n";
+        let rewritten = "db.host=new-db\r\n# new-db stays in comments too\nport=5432\n";
+
+        for backup in [true, false] {
+            let file = dir.join(format!("app-{}.properties", backup));
+            std::fs::write(&file, original).unwrap();
+
+            let results = quiet_tool(2).replace_in_files(&["old-(db)".to_string()], "new-$1", &dir, backup).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].line_content, "2 replacements");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), rewritten);
+
+            let backup_file = dir.join(format!("app-{}.properties.bak", backup));
+            match backup {
+                true => assert_eq!(std::fs::read_to_string(&backup_file).unwrap(), original),
+                false => assert!(!backup_file.exists()),
+            }
+            assert!(!dir.join(format!("app-{}.properties{}", backup, REPLACE_TEMP_SUFFIX)).exists());
+            std::fs::remove_file(&file).unwrap();
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn overlapping_context_windows_show_each_line_once() {
+        let lines: Vec<String> = (1..=9).map(|n| format!("line {}", n)).collect();
+        let mut tracker = ContextTracker::new(2, 2);
+        let mut results = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let number = i + 1;
+            // Matches on 3 and 5: 5's leading context overlaps 3's trailing context
+            let result = [3, 5].contains(&number).then(|| SearchResult {
+                line_number: Some(number),
+                line_content: line.clone(),
+                ..SearchResult::at(&Location::file("app.log"))
+            });
+            tracker.push(line, number, result, &mut |r| results.push(r));
+        }
+        tracker.finish(&mut |r| results.push(r));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].context_start, results[0].context.as_ref().unwrap().len()), (Some(1), 5));
+        assert_eq!((results[1].context_start, results[1].context.as_ref().unwrap().len()), (Some(3), 5));
+
+        // What print_results() shows: lines 1-7 once each, in order
+        let mut shown_through = 0;
+        let mut printed = Vec::new();
+        for result in &results {
+            let context = result.context.as_ref().unwrap();
+            printed.extend(unshown_context(context, result.context_start.unwrap(), shown_through).map(|(n, line)| (n, line.clone())));
+            shown_through = last_context_line(result).max(shown_through);
+        }
+        let expected: Vec<(usize, String)> = (1..=7).map(|n| (n, format!("line {}", n))).collect();
+        assert_eq!(printed, expected);
+    }
+
+    #[test]
+    fn batch_content_queries_match_like_search() {
+        let dir = scratch_dir("batch-content");
+        let jar_path = dir.join("notes.jar");
+        let mut jar = zip::ZipWriter::new(File::create(&jar_path).unwrap());
+        jar.start_file("notes.txt", zip::write::FileOptions::default()).unwrap();
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("before\nthe secret token\nafter\nsecrets elsewhere\n".encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        jar.write_all(&utf16).unwrap();
+        jar.finish().unwrap();
+
+        let tool = quiet_tool(1).with_whole_word(true).with_context_lines(1, 1);
+        let queries = vec![BatchQuery::new("content", "secret", false).unwrap()];
+        let results = tool.run_queries(&queries, &dir).unwrap();
+
+        // Decoded from UTF-16, "secrets" rejected by --word, and context kept
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, Some(2));
+        assert_eq!(results[0].line_content, "the secret token");
+        assert_eq!(results[0].query.as_deref(), Some("content secret"));
+        let context = results[0].context.as_deref().unwrap();
+        assert!(context.contains(&"before".to_string()) && context.contains(&"after".to_string()));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
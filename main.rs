@@ -1,995 +1,23 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
 use colored::*;
-use csv::Writer;
-use rayon::prelude::*;
-use regex::Regex;
-use std::collections::HashMap;
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Read, Write};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::FuzzySelect;
+use clap::parser::ValueSource;
+use jartool::checksum::{ChecksumAlgorithm, ExpectedChecksum};
+use jartool::config::Config;
+use jartool::maven::Advisory;
+use jartool::reflection::ReflectionSymbol;
+use jartool::remote;
+use jartool::{read_file_list, read_pattern_file, BatchQuery, JarTool, ModuleFilter, SearchResult};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::any::Any;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-use walkdir::WalkDir;
-use zip::ZipArchive;
-use std::collections::HashSet;
-
-#[derive(Debug, Clone)]
-pub struct SearchResult {
-    pub file_location: String,
-    pub line_number: Option<usize>,
-    pub line_content: String,
-    pub match_type: String,
-}
-
-#[derive(Debug, Default)]
-pub struct SearchStats {
-    pub total_jars: usize,
-    pub total_zip_files: usize,
-    pub total_class_files: usize,
-    pub total_java_files: usize,
-    pub total_other_files: usize,
-    pub matches_found: usize,
-    pub files_processed: usize,
-    pub elapsed_time: Duration,
-}
-
-#[derive(Debug)]
-pub struct JarTool {
-    stats: Arc<Mutex<SearchStats>>,
-    results: Arc<Mutex<Vec<SearchResult>>>,
-    verbose: bool,
-    size_threshold: u64,
-    parallel_jobs: usize,
-    excludes: HashSet<String>,
-    mini_mode: bool,
-    unique_files: Arc<Mutex<HashSet<String>>>,
-}
-
-impl JarTool {
-    pub fn new(verbose: bool, size_threshold: u64, parallel_jobs: Option<usize>, excludes: Vec<String>, mini_mode: bool) -> Self {
-        let jobs = parallel_jobs.unwrap_or_else(|| num_cpus::get());
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(jobs)
-            .build_global()
-            .expect("Failed to build thread pool");
-
-        let exclude_set: HashSet<String> = excludes.into_iter().collect();
-
-        Self {
-            stats: Arc::new(Mutex::new(SearchStats::default())),
-            results: Arc::new(Mutex::new(Vec::new())),
-            verbose,
-            size_threshold,
-            parallel_jobs: jobs,
-            excludes: exclude_set,
-            mini_mode,
-            unique_files: Arc::new(Mutex::new(HashSet::new())),
-        }
-    }
-
-
-    fn should_exclude_path(&self, path: &Path) -> bool {
-        let path_str = path.to_string_lossy();
-        for exclude in &self.excludes {
-            if path_str.contains(exclude) {
-                self.log_verbose(&format!("Excluding path: {} (matches: {})", path_str, exclude));
-                return true;
-            }
-        }
-        false
-    }
-
-    fn log_verbose(&self, msg: &str) {
-        if self.verbose {
-            eprintln!("{} {}", "[DEBUG]".blue(), msg);
-        }
-    }
-
-    fn update_stats<F>(&self, updater: F)
-    where
-        F: FnOnce(&mut SearchStats),
-    {
-        if let Ok(mut stats) = self.stats.lock() {
-            updater(&mut stats);
-        }
-    }
-
-    fn add_result(&self, result: SearchResult) {
-        if self.mini_mode {
-            // In mini mode, only add unique file locations
-            let file_location = result.file_location.clone();
-            if let Ok(mut unique_files) = self.unique_files.lock() {
-                if unique_files.insert(file_location.clone()) {
-                    // This is a new file, add a simplified result
-                    let mini_result = SearchResult {
-                        file_location,
-                        line_number: None,
-                        line_content: "Found matches".to_string(),
-                        match_type: result.match_type,
-                    };
-                    if let Ok(mut results) = self.results.lock() {
-                        results.push(mini_result);
-                    }
-                }
-            }
-        } else {
-            // Normal mode, add all results
-            if let Ok(mut results) = self.results.lock() {
-                results.push(result);
-            }
-        }
-        self.update_stats(|stats| stats.matches_found += 1);
-    }
-
-    pub fn search_exact_class(&self, query: &str, search_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        self.log_verbose(&format!("Starting exact class search for: {}", query));
-        let start_time = Instant::now();
-
-        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
-        self.update_stats(|stats| stats.total_jars = jar_files.len());
-
-        println!("{} Found {} JAR files to process", "INFO".green(), jar_files.len());
-
-        jar_files.par_iter().for_each(|jar_path| {
-            self.search_class_in_jar(jar_path, query, true);
-        });
-
-        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
-        Ok(())
-    }
-
-    pub fn search_class_substring(&self, query: &str, search_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        self.log_verbose(&format!("Starting class substring search for: {}", query));
-        let start_time = Instant::now();
-
-        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
-        self.update_stats(|stats| stats.total_jars = jar_files.len());
-
-        println!("{} Found {} JAR files to process", "INFO".green(), jar_files.len());
-
-        jar_files.par_iter().for_each(|jar_path| {
-            self.search_class_in_jar(jar_path, query, false);
-        });
-
-        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
-        Ok(())
-    }
-
-    pub fn search_package(&self, package: &str, search_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        self.log_verbose(&format!("Starting package search for: {}", package));
-        let start_time = Instant::now();
-
-        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
-        self.update_stats(|stats| stats.total_jars = jar_files.len());
-
-        let package_path = package.replace('.', "/");
-        
-        jar_files.par_iter().for_each(|jar_path| {
-            self.search_package_in_jar(jar_path, &package_path);
-        });
-
-        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
-        Ok(())
-    }
-
-    pub fn search_content(&self, pattern: &str, search_dir: &Path, file_types: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
-        self.log_verbose(&format!("Starting content search for: {}", pattern));
-        let start_time = Instant::now();
-
-        let regex = Regex::new(pattern)?;
-        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
-        self.update_stats(|stats| stats.total_jars = jar_files.len());
-
-        println!("{} Found {} JAR files to process", "INFO".green(), jar_files.len());
-
-        jar_files.par_iter().for_each(|jar_path| {
-            self.search_content_in_jar(jar_path, &regex, file_types);
-        });
-
-        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
-        Ok(())
-    }
-
-    pub fn search_java_files(&self, pattern: &str, search_dir: &Path, content_search: bool) -> Result<(), Box<dyn std::error::Error>> {
-        self.log_verbose(&format!("Starting Java file search for: {}", pattern));
-        let start_time = Instant::now();
-
-        let regex = if content_search {
-            Some(Regex::new(pattern)?)
-        } else {
-            Some(Regex::new(&format!(".*{}.*", regex::escape(pattern)))?)
-        };
-
-        let java_files: Vec<PathBuf> = WalkDir::new(search_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "java"))
-            .map(|e| e.path().to_path_buf())
-            .collect();
-
-        self.update_stats(|stats| stats.total_java_files = java_files.len());
-        println!("{} Found {} Java files to process", "INFO".green(), java_files.len());
-
-        if content_search {
-            java_files.par_iter().for_each(|java_path| {
-                if let Some(ref regex) = regex {
-                    self.search_content_in_file(java_path, regex);
-                }
-            });
-        } else {
-            for java_path in &java_files {
-                if let Some(filename) = java_path.file_name() {
-                    if let Some(ref regex) = regex {
-                        if regex.is_match(&filename.to_string_lossy()) {
-                            let result = SearchResult {
-                                file_location: java_path.display().to_string(),
-                                line_number: None,
-                                line_content: "Java file name match".to_string(),
-                                match_type: "java_filename".to_string(),
-                            };
-                            self.add_result(result);
-                        }
-                    }
-                }
-            }
-        }
-
-        self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
-        Ok(())
-    }
-
-fn search_content_in_all_files(&self, file_path: &Path, regex: &Regex) {
-    if !self.should_process_file(file_path) {
-        return;
-    }
-
-    let file_ext = file_path.extension()
-        .map(|ext| ext.to_string_lossy().to_lowercase())
-        .unwrap_or_else(|| "no_extension".to_string());
-
-    self.log_verbose(&format!("Processing {} file: {}", file_ext, file_path.display()));
-
-    // Try to read as text first
-    if let Ok(file) = File::open(file_path) {
-        let reader = BufReader::new(file);
-        let mut found_text_match = false;
-        
-        // First attempt: read as UTF-8 text
-        for (line_num, line_result) in reader.lines().enumerate() {
-            match line_result {
-                Ok(line) => {
-                    if regex.is_match(&line) {
-                        let result = SearchResult {
-                            file_location: file_path.display().to_string(),
-                            line_number: Some(line_num + 1),
-                            line_content: line.trim().to_string(),
-                            match_type: self.get_file_type(file_path),
-                        };
-                        self.add_result(result);
-                        found_text_match = true;
-                    }
-                },
-                Err(_) => {
-                    // If we encounter a read error (likely binary or encoding issue), 
-                    // try binary search for remaining content
-                    if !found_text_match {
-                        self.log_verbose(&format!("Text read failed for {}, trying binary search", file_path.display()));
-                        self.search_binary_file(file_path, regex);
-                    }
-                    break;
-                }
-            }
-        }
-        
-        self.update_stats(|stats| stats.files_processed += 1);
-    } else {
-        self.log_verbose(&format!("Failed to open file: {}", file_path.display()));
-    }
-}
-
-
-
-fn search_binary_file(&self, file_path: &Path, regex: &Regex) {
-    if let Ok(mut file) = File::open(file_path) {
-        let mut buffer = Vec::new();
-        if file.read_to_end(&mut buffer).is_ok() {
-            // Extract strings from binary data (similar to strings command)
-            let mut current_string = String::new();
-            let mut in_string = false;
-            
-            for &byte in &buffer {
-                if byte.is_ascii_graphic() || byte == b' ' || byte == b'\t' {
-                    current_string.push(byte as char);
-                    in_string = true;
-                } else {
-                    if in_string && current_string.len() >= 4 {
-                        if regex.is_match(&current_string) {
-                            let result = SearchResult {
-                                file_location: file_path.display().to_string(),
-                                line_number: None,
-                                line_content: current_string.clone(),
-                                match_type: format!("{}_binary", self.get_file_type(file_path)),
-                            };
-                            self.add_result(result);
-                        }
-                    }
-                    current_string.clear();
-                    in_string = false;
-                }
-            }
-            
-            // Check final string
-            if in_string && current_string.len() >= 4 && regex.is_match(&current_string) {
-                let result = SearchResult {
-                    file_location: file_path.display().to_string(),
-                    line_number: None,
-                    line_content: current_string,
-                    match_type: format!("{}_binary", self.get_file_type(file_path)),
-                };
-                self.add_result(result);
-            }
-        }
-    }
-}
-
-pub fn master_search(&self, pattern: &str, search_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    println!("{} Starting master search mode for: {}", "MASTER".yellow().bold(), pattern);
-    if self.mini_mode {
-        println!("{} Mini mode: showing unique files only", "MODE".purple());
-    }
-    println!("{} Processing ALL file types (.properties, .bat, .conf, .xml, etc.)", "INFO".green());
-    
-    let start_time = Instant::now();
-    let regex = Regex::new(pattern)?;
-
-    // Find all types of files with exclusion filtering
-    let all_files: Vec<PathBuf> = WalkDir::new(search_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .filter(|e| !self.should_exclude_path(e.path())) // Add exclusion filter
-        .map(|e| e.path().to_path_buf())
-        .collect();
-
-    let mut jar_files = Vec::new();
-    let mut zip_files = Vec::new();
-    let mut java_files = Vec::new();
-    let mut config_files = Vec::new();
-    let mut script_files = Vec::new();
-    let mut xml_files = Vec::new();
-    let mut text_files = Vec::new();
-    let mut other_files = Vec::new();
-
-    // Categorize files by type for better reporting
-    for file in all_files {
-        if let Some(ext) = file.extension() {
-            match ext.to_str() {
-                Some("jar") => jar_files.push(file),
-                Some("zip") | Some("war") | Some("ear") => zip_files.push(file),
-                Some("java") => java_files.push(file),
-                Some("properties") | Some("conf") | Some("config") | Some("cfg") | Some("ini") => config_files.push(file),
-                Some("bat") | Some("cmd") | Some("sh") | Some("ps1") | Some("py") | Some("rb") => script_files.push(file),
-                Some("xml") | Some("xsd") | Some("xsl") | Some("xslt") => xml_files.push(file),
-                Some("txt") | Some("md") | Some("log") | Some("yaml") | Some("yml") | Some("json") => text_files.push(file),
-                _ => other_files.push(file),
-            }
-        } else {
-            // Process files without extensions too
-            other_files.push(file);
-        }
-    }
-
-    // Combine all non-archive files for processing
-    let mut all_other_files = Vec::new();
-    all_other_files.extend(config_files.iter().cloned());
-    all_other_files.extend(script_files.iter().cloned());
-    all_other_files.extend(xml_files.iter().cloned());
-    all_other_files.extend(text_files.iter().cloned());
-    all_other_files.extend(other_files.iter().cloned());
-
-    self.update_stats(|stats| {
-        stats.total_jars = jar_files.len();
-        stats.total_zip_files = zip_files.len();
-        stats.total_java_files = java_files.len();
-        stats.total_other_files = all_other_files.len();
-    });
-
-    println!("{} File analysis:", "INFO".green());
-    println!("  JAR files: {}", jar_files.len());
-    println!("  ZIP files: {}", zip_files.len());
-    println!("  Java files: {}", java_files.len());
-    println!("  Config files (.properties, .conf, .ini): {}", config_files.len());
-    println!("  Script files (.bat, .sh, .py, etc.): {}", script_files.len());
-    println!("  XML files (.xml, .xsd, etc.): {}", xml_files.len());
-    println!("  Text files (.txt, .json, .yaml, etc.): {}", text_files.len());
-    println!("  Other files: {}", other_files.len());
-    println!("  {} Total files to process: {}", "TOTAL".cyan(), 
-        jar_files.len() + zip_files.len() + java_files.len() + all_other_files.len());
-
-    // Search in JAR files
-    if !jar_files.is_empty() {
-        println!("{} Searching in JAR files...", "PHASE".cyan());
-        jar_files.par_iter().for_each(|jar_path| {
-            self.search_content_in_jar(jar_path, &regex, &["*"]);
-        });
-    }
-
-    // Search in ZIP files
-    if !zip_files.is_empty() {
-        println!("{} Searching in ZIP files...", "PHASE".cyan());
-        zip_files.par_iter().for_each(|zip_path| {
-            self.search_content_in_zip(zip_path, &regex);
-        });
-    }
-
-    // Search in Java files
-    if !java_files.is_empty() {
-        println!("{} Searching in Java files...", "PHASE".cyan());
-        java_files.par_iter().for_each(|java_path| {
-            self.search_content_in_file(java_path, &regex);
-        });
-    }
-
-    // Search in ALL other files (config, scripts, XML, text, etc.)
-    if !all_other_files.is_empty() {
-        println!("{} Searching in configuration, script, and other files (.properties, .bat, .conf, .xml, etc.)...", "PHASE".cyan());
-        all_other_files.par_iter().for_each(|file_path| {
-            self.search_content_in_all_files(file_path, &regex);
-        });
-    }
-
-    self.update_stats(|stats| stats.elapsed_time = start_time.elapsed());
-    println!("{} Master search completed!", "SUCCESS".green());
-    Ok(())
-}
-    fn search_class_in_jar(&self, jar_path: &Path, query: &str, exact_match: bool) {
-        if !self.should_process_file(jar_path) {
-            return;
-        }
-
-        self.log_verbose(&format!("Processing JAR: {}", jar_path.display()));
-
-        if let Ok(file) = File::open(jar_path) {
-            if let Ok(mut archive) = ZipArchive::new(file) {
-                let mut class_count = 0;
-                
-                for i in 0..archive.len() {
-                    if let Ok(file_in_zip) = archive.by_index(i) {
-                        let file_name = file_in_zip.name();
-                        
-                        if file_name.ends_with(".class") {
-                            class_count += 1;
-                            
-                            let class_name = file_name
-                                .strip_suffix(".class")
-                                .unwrap_or(file_name)
-                                .replace('/', ".");
-                            
-                            let matches = if exact_match {
-                                class_name.ends_with(&format!(".{}", query)) || class_name == query
-                            } else {
-                                class_name.contains(query)
-                            };
-                            
-                            if matches {
-                                let result = SearchResult {
-                                    file_location: format!("{}:{}", jar_path.display(), file_name),
-                                    line_number: None,
-                                    line_content: class_name,
-                                    match_type: "class".to_string(),
-                                };
-                                self.add_result(result);
-                            }
-                        }
-                    }
-                }
-                
-                self.update_stats(|stats| {
-                    stats.files_processed += 1;
-                    stats.total_class_files += class_count;
-                });
-            }
-        }
-    }
+use std::sync::mpsc;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
 
-    fn search_package_in_jar(&self, jar_path: &Path, package_path: &str) {
-        if !self.should_process_file(jar_path) {
-            return;
-        }
-
-        if let Ok(file) = File::open(jar_path) {
-            if let Ok(mut archive) = ZipArchive::new(file) {
-                for i in 0..archive.len() {
-                    if let Ok(file_in_zip) = archive.by_index(i) {
-                        let file_name = file_in_zip.name();
-                        
-                        if file_name.ends_with(".class") && file_name.starts_with(package_path) {
-                            let class_name = file_name
-                                .strip_suffix(".class")
-                                .unwrap_or(file_name)
-                                .replace('/', ".");
-                            
-                            let result = SearchResult {
-                                file_location: format!("{}:{}", jar_path.display(), file_name),
-                                line_number: None,
-                                line_content: class_name,
-                                match_type: "package".to_string(),
-                            };
-                            self.add_result(result);
-                        }
-                    }
-                }
-                self.update_stats(|stats| stats.files_processed += 1);
-            }
-        }
-    }
-
-    fn search_content_in_jar(&self, jar_path: &Path, regex: &Regex, file_types: &[&str]) {
-        if !self.should_process_file(jar_path) {
-            return;
-        }
-
-        self.log_verbose(&format!("Searching content in JAR: {}", jar_path.display()));
-
-        if let Ok(file) = File::open(jar_path) {
-            if let Ok(mut archive) = ZipArchive::new(file) {
-                let mut counts = (0, 0, 0); // (classes, java, others)
-                
-                for i in 0..archive.len() {
-                    if let Ok(mut file_in_zip) = archive.by_index(i) {
-                        let file_name = file_in_zip.name().to_string();
-                        
-                        // Skip directories
-                        if file_name.ends_with('/') {
-                            continue;
-                        }
-
-                        // Count file types
-                        if file_name.ends_with(".class") {
-                            counts.0 += 1;
-                        } else if file_name.ends_with(".java") {
-                            counts.1 += 1;
-                        } else {
-                            counts.2 += 1;
-                        }
-
-                        // Check if we should search this file type
-                        let should_search = file_types.contains(&"*") || 
-                            (file_types.contains(&"class") && file_name.ends_with(".class")) ||
-                            (file_types.contains(&"java") && file_name.ends_with(".java")) ||
-                            (file_types.contains(&"other") && !file_name.ends_with(".class") && !file_name.ends_with(".java"));
-
-                        if should_search {
-                            if file_name.ends_with(".class") {
-                                // For class files, use strings-like extraction for bytecode
-                                self.search_in_binary_content(&mut file_in_zip, regex, jar_path, &file_name);
-                            } else {
-                                // For text files, search line by line
-                                self.search_in_text_content(&mut file_in_zip, regex, jar_path, &file_name);
-                            }
-                        }
-                    }
-                }
-                
-                self.update_stats(|stats| {
-                    stats.files_processed += 1;
-                    stats.total_class_files += counts.0;
-                    stats.total_java_files += counts.1;
-                    stats.total_other_files += counts.2;
-                });
-            }
-        }
-    }
-
-    fn search_content_in_zip(&self, zip_path: &Path, regex: &Regex) {
-        if !self.should_process_file(zip_path) {
-            return;
-        }
-
-        self.log_verbose(&format!("Searching content in ZIP: {}", zip_path.display()));
-
-        if let Ok(file) = File::open(zip_path) {
-            if let Ok(mut archive) = ZipArchive::new(file) {
-                for i in 0..archive.len() {
-                    if let Ok(mut file_in_zip) = archive.by_index(i) {
-                        let file_name = file_in_zip.name().to_string();
-                        
-                        if !file_name.ends_with('/') {
-                            self.search_in_text_content(&mut file_in_zip, regex, zip_path, &file_name);
-                        }
-                    }
-                }
-                self.update_stats(|stats| stats.files_processed += 1);
-            }
-        }
-    }
-
-    fn search_content_in_file(&self, file_path: &Path, regex: &Regex) {
-        if !self.should_process_file(file_path) {
-            return;
-        }
-
-        if let Ok(file) = File::open(file_path) {
-            let reader = BufReader::new(file);
-            
-            for (line_num, line_result) in reader.lines().enumerate() {
-                if let Ok(line) = line_result {
-                    if regex.is_match(&line) {
-                        let result = SearchResult {
-                            file_location: file_path.display().to_string(),
-                            line_number: Some(line_num + 1),
-                            line_content: line.trim().to_string(),
-                            match_type: self.get_file_type(file_path),
-                        };
-                        self.add_result(result);
-                    }
-                }
-            }
-            self.update_stats(|stats| stats.files_processed += 1);
-        }
-    }
-
-    fn search_in_text_content<R: Read>(&self, reader: &mut R, regex: &Regex, archive_path: &Path, file_name: &str) {
-        let mut buffer = String::new();
-        if reader.read_to_string(&mut buffer).is_ok() {
-            for (line_num, line) in buffer.lines().enumerate() {
-                if regex.is_match(line) {
-                    let result = SearchResult {
-                        file_location: format!("{}:{}", archive_path.display(), file_name),
-                        line_number: Some(line_num + 1),
-                        line_content: line.trim().to_string(),
-                        match_type: self.get_archive_file_type(file_name),
-                    };
-                    self.add_result(result);
-                }
-            }
-        }
-    }
-
-    fn search_in_binary_content<R: Read>(&self, reader: &mut R, regex: &Regex, archive_path: &Path, file_name: &str) {
-        let mut buffer = Vec::new();
-        if reader.read_to_end(&mut buffer).is_ok() {
-            // Extract strings from binary data (similar to strings command)
-            let mut current_string = String::new();
-            let mut in_string = false;
-            
-            for &byte in &buffer {
-                if byte.is_ascii_graphic() || byte == b' ' || byte == b'\t' {
-                    current_string.push(byte as char);
-                    in_string = true;
-                } else {
-                    if in_string && current_string.len() >= 4 {
-                        if regex.is_match(&current_string) {
-                            let result = SearchResult {
-                                file_location: format!("{}:{}", archive_path.display(), file_name),
-                                line_number: None,
-                                line_content: current_string.clone(),
-                                match_type: "class_bytecode".to_string(),
-                            };
-                            self.add_result(result);
-                        }
-                    }
-                    current_string.clear();
-                    in_string = false;
-                }
-            }
-            
-            // Check final string
-            if in_string && current_string.len() >= 4 && regex.is_match(&current_string) {
-                let result = SearchResult {
-                    file_location: format!("{}:{}", archive_path.display(), file_name),
-                    line_number: None,
-                    line_content: current_string,
-                    match_type: "class_bytecode".to_string(),
-                };
-                self.add_result(result);
-            }
-        }
-    }
-
-    fn find_archive_files(&self, search_dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
-        let files: Vec<PathBuf> = WalkDir::new(search_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .filter(|e| !self.should_exclude_path(e.path())) // Add exclusion filter
-            .filter(|e| {
-                if let Some(ext) = e.path().extension() {
-                    extensions.iter().any(|&target_ext| {
-                        ext.to_string_lossy().to_lowercase() == target_ext.to_lowercase()
-                    })
-                } else {
-                    false
-                }
-            })
-            .map(|e| e.path().to_path_buf())
-            .collect();
-
-        Ok(files)
-    }
-
-    fn should_process_file(&self, file_path: &Path) -> bool {
-        self.log_verbose(&format!("The size threshold is set to {} bytes", self.size_threshold));
-        
-        // Check exclusions first
-        if self.should_exclude_path(file_path) {
-            return false;
-        }
-
-        if let Ok(metadata) = file_path.metadata() {
-            if (self.size_threshold == 0) {
-                self.log_verbose(&format!("Processing file without size threshold: {}", file_path.display()));
-                return true; // No size threshold, process all files
-            }
-            if metadata.len() < self.size_threshold {
-                self.log_verbose(&format!("Skipping small file: {} ({} bytes)", 
-                    file_path.display(), metadata.len()));
-                return false;
-            }
-        }
-        true
-    }
-
-    fn is_text_file(&self, file_path: &Path) -> bool {
-        // Simple heuristic: check first few bytes
-        if let Ok(mut file) = File::open(file_path) {
-            let mut buffer = [0; 1024];
-            if let Ok(bytes_read) = file.read(&mut buffer) {
-                if bytes_read == 0 {
-                    return false;
-                }
-                
-                // Check for null bytes (binary files usually have them)
-                let null_count = buffer[..bytes_read].iter().filter(|&&b| b == 0).count();
-                let null_ratio = null_count as f64 / bytes_read as f64;
-                
-                // If more than 10% null bytes, probably binary
-                return null_ratio < 0.1;
-            }
-        }
-        false
-    }
-
-fn get_file_type(&self, file_path: &Path) -> String {
-    if let Some(ext) = file_path.extension() {
-        let ext_str = ext.to_string_lossy().to_lowercase();
-        match ext_str.as_str() {
-            "properties" => "properties_config".to_string(),
-            "conf" | "config" | "cfg" => "configuration".to_string(),
-            "bat" | "cmd" => "batch_script".to_string(),
-            "sh" => "shell_script".to_string(),
-            "xml" | "xsd" | "xsl" | "xslt" => "xml_document".to_string(),
-            "json" => "json_data".to_string(),
-            "yaml" | "yml" => "yaml_data".to_string(),
-            "ini" => "ini_config".to_string(),
-            "log" => "log_file".to_string(),
-            "txt" => "text_file".to_string(),
-            "md" => "markdown".to_string(),
-            "py" => "python_script".to_string(),
-            "rb" => "ruby_script".to_string(),
-            "ps1" => "powershell_script".to_string(),
-            _ => ext_str,
-        }
-    } else {
-        "no_extension".to_string()
-    }
-}
-
-    fn get_archive_file_type(&self, file_name: &str) -> String {
-        if let Some(ext) = file_name.split('.').last() {
-            ext.to_string()
-        } else {
-            "unknown".to_string()
-        }
-    }
-
-    pub fn export_csv(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(filename)?;
-
-        let mut writer = Writer::from_writer(file);
-        writer.write_record(&["file_location", "line", "line_content", "match_type"])?;
-
-        if let Ok(results) = self.results.lock() {
-            for result in results.iter() {
-                writer.write_record(&[
-                    &result.file_location,
-                    &result.line_number.map_or(String::new(), |n| n.to_string()),
-                    &result.line_content,
-                    &result.match_type,
-                ])?;
-            }
-        }
-
-        writer.flush()?;
-        println!("{} Results exported to {}", "SUCCESS".green(), filename);
-        Ok(())
-    }
-
-      pub fn print_stats(&self) {
-        if let Ok(stats) = self.stats.lock() {
-            let results_count = self.results.lock().map(|r| r.len()).unwrap_or(0);
-            let unique_count = if self.mini_mode {
-                self.unique_files.lock().map(|u| u.len()).unwrap_or(0)
-            } else {
-                results_count
-            };
-
-            println!("\n{}", "═══════════════════════════════════════════════════════════════".white());
-            println!("{}", "                        SEARCH STATISTICS                        ".white());
-            println!("{}", "═══════════════════════════════════════════════════════════════".white());
-            
-            println!("{:<25} {:>10}", "JAR files scanned:".cyan(), format!("{}", stats.total_jars).white());
-            println!("{:<25} {:>10}", "ZIP files scanned:".cyan(), format!("{}", stats.total_zip_files).white());
-            println!("{:<25} {:>10}", "Class files found:".cyan(), format!("{}", stats.total_class_files).white());
-            println!("{:<25} {:>10}", "Java files found:".cyan(), format!("{}", stats.total_java_files).white());
-            println!("{:<25} {:>10}", "Other files found:".cyan(), format!("{}", stats.total_other_files).white());
-            println!("{:<25} {:>10}", "Total files processed:".cyan(), format!("{}", stats.files_processed).white());
-            
-            if self.mini_mode {
-                println!("{:<25} {:>10}", "Unique files w/ matches:".cyan(), format!("{}", unique_count).green());
-                println!("{:<25} {:>10}", "Total matches found:".cyan(), format!("{}", stats.matches_found).yellow());
-            } else {
-                println!("{:<25} {:>10}", "Matches found:".cyan(), format!("{}", results_count).green());
-            }
-            
-            println!("{:<25} {:>10}", "Elapsed time:".cyan(), format!("{:.2}s", stats.elapsed_time.as_secs_f64()).yellow());
-            
-            if stats.elapsed_time.as_secs_f64() > 0.0 {
-                let files_per_sec = stats.files_processed as f64 / stats.elapsed_time.as_secs_f64();
-                let classes_per_sec = stats.total_class_files as f64 / stats.elapsed_time.as_secs_f64();
-                println!("{:<25} {:>10}", "Files/second:".cyan(), format!("{:.2}", files_per_sec).purple());
-                println!("{:<25} {:>10}", "Classes/second:".cyan(), format!("{:.2}", classes_per_sec).purple());
-            }
-            
-            println!("{:<25} {:>10}", "Parallel jobs:".cyan(), format!("{}", self.parallel_jobs).white());
-            println!("{:<25} {:>10}", "Mode:".cyan(), if self.mini_mode { "Mini (unique files)".purple() } else { "Full".white() });
-            
-            if !self.excludes.is_empty() {
-                println!("{:<25} {:>10}", "Exclusions:".cyan(), format!("{}", self.excludes.len()).red());
-                for exclude in &self.excludes {
-                    println!("  {}", exclude.red());
-                }
-            }
-            
-            println!("{}", "═══════════════════════════════════════════════════════════════".white());
-        }
-    }
-
-
-    pub fn list_jars(&self, search_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        println!("{}", "JAR Analysis Report".white());
-        println!("{}", "==================".cyan());
-
-        let jar_files = self.find_archive_files(search_dir, &["jar"])?;
-        
-        if jar_files.is_empty() {
-            println!("{} No JAR files found in {}", "ERROR".red(), search_dir.display());
-            return Ok(());
-        }
-
-        println!("{} Found {} JAR files", "INFO".blue(), jar_files.len());
-        println!();
-
-        println!("{:<50} {:>10} {:>10} {:>10} {:>10}", 
-            "JAR File", "Classes", "Java", "Files", "Size (MB)");
-        println!("{:<50} {:>10} {:>10} {:>10} {:>10}", 
-            "--------", "-------", "----", "-----", "---------");
-
-        let mut total_stats = (0, 0, 0, 0u64); // (classes, java, files, size)
-
-        for jar_path in &jar_files {
-            if let Ok(metadata) = jar_path.metadata() {
-                let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
-                let (class_count, java_count, file_count) = self.count_jar_contents(jar_path);
-
-                let jar_name = jar_path.file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy();
-                
-                let display_name = if jar_name.len() > 47 {
-                    format!("{}...", &jar_name[..44])
-                } else {
-                    jar_name.to_string()
-                };
-
-                println!("{:<50} {:>10} {:>10} {:>10} {:>10.2}", 
-                    display_name, class_count, java_count, file_count, size_mb);
-
-                total_stats.0 += class_count;
-                total_stats.1 += java_count;
-                total_stats.2 += file_count;
-                total_stats.3 += metadata.len();
-            }
-        }
-
-        println!();
-        println!("{:<50} {:>10} {:>10} {:>10} {:>10.2}", 
-            "TOTAL", total_stats.0, total_stats.1, total_stats.2, 
-            total_stats.3 as f64 / (1024.0 * 1024.0));
-
-        Ok(())
-    }
-
-    fn count_jar_contents(&self, jar_path: &Path) -> (usize, usize, usize) {
-        let mut class_count = 0;
-        let mut java_count = 0;
-        let mut file_count = 0;
-
-        if let Ok(file) = File::open(jar_path) {
-            if let Ok(mut archive) = ZipArchive::new(file) {
-                for i in 0..archive.len() {
-                    if let Ok(file_in_zip) = archive.by_index(i) {
-                        let file_name = file_in_zip.name();
-                        
-                        if !file_name.ends_with('/') {
-                            file_count += 1;
-                            if file_name.ends_with(".class") {
-                                class_count += 1;
-                            } else if file_name.ends_with(".java") {
-                                java_count += 1;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        (class_count, java_count, file_count)
-    }
-
-     pub fn print_results(&self) {
-        if let Ok(results) = self.results.lock() {
-            if results.is_empty() {
-                println!("{} No matches found", "RESULT".yellow());
-                return;
-            }
-
-            println!("\n{} Found {} {}", 
-                "RESULTS".green().bold(), 
-                results.len(),
-                if self.mini_mode { "unique files with matches" } else { "matches" }
-            );
-            println!("{}", "─".repeat(80).cyan());
-
-            for (i, result) in results.iter().enumerate() {
-                if self.mini_mode {
-                    // Mini mode: simple file listing
-                    println!("{:>3}. {}", (i + 1).to_string().white(), result.file_location.green());
-                } else {
-                    // Full mode: detailed results
-                    if let Some(line_num) = result.line_number {
-                        println!("{:>3}. {} {}:{}", 
-                            (i + 1).to_string().white(),
-                            result.file_location.green(),
-                            "line".cyan(),
-                            line_num.to_string().yellow()
-                        );
-                        println!("     {}: {}", 
-                            result.match_type.purple(),
-                            result.line_content.white()
-                        );
-                    } else {
-                        println!("{:>3}. {} {}: {}", 
-                            (i + 1).to_string().white(),
-                            result.file_location.green(),
-                            result.match_type.purple(),
-                            result.line_content.white()
-                        );
-                    }
-                }
-            }
-        }
-    }
-
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> ExitCode {
     let matches = Command::new("jartool")
         .version("4.0")
         .author("Rust JarTool - Ultra-fast JAR & Java analysis")
@@ -999,114 +27,948 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .long("class")
             .value_name("CLASS_NAME")
             .help("Search for exact class name")
-            .conflicts_with_all(&["class_substring", "package", "content", "method", "java_files", "java_content", "master"]))
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
         .arg(Arg::new("class_substring")
             .short('C')
             .long("class-contains")
             .value_name("SUBSTRING")
             .help("Search for substring in class names")
-            .conflicts_with_all(&["exact_class", "package", "content", "method", "java_files", "java_content", "master"]))
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
+        .arg(Arg::new("class_regex")
+            .long("class-regex")
+            .value_name("PATTERN")
+            .help("Search for classes whose fully-qualified name matches a regex")
+            .group("operation"))
         .arg(Arg::new("package")
             .short('p')
             .long("package")
             .value_name("PACKAGE")
             .help("Search by package name")
-            .conflicts_with_all(&["exact_class", "class_substring", "content", "method", "java_files", "java_content", "master"]))
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
         .arg(Arg::new("content")
             .short('s')
             .long("search")
             .value_name("PATTERN")
             .help("Search string inside class bytecode (regex supported)")
-            .conflicts_with_all(&["exact_class", "class_substring", "package", "method", "java_files", "java_content", "master"]))
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
         .arg(Arg::new("master")
             .short('m')
             .long("master")
             .value_name("PATTERN")
             .help("Master search: search everywhere (JAR, ZIP, Java, text files)")
-            .conflicts_with_all(&["exact_class", "class_substring", "package", "content", "method", "java_files", "java_content"]))
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
+        .arg(Arg::new("references")
+            .short('r')
+            .long("references")
+            .visible_alias("depends-on")
+            .value_name("CLASS_NAME")
+            .help("Find classes whose constant pool references the given fully-qualified class")
+            .group("operation"))
+        .arg(Arg::new("reflection")
+            .long("reflection")
+            .help("Find classes using reflection APIs (Class.forName, Method.invoke, setAccessible, ...)")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("reflection_symbols")
+            .long("reflection-symbols")
+            .value_name("FILE")
+            .help("With --reflection, watch the symbols in FILE (pkg.Class#method, pkg.Class or \"text\" per line) instead of the built-in list")
+            .requires("reflection"))
+        .arg(Arg::new("method")
+            .long("method")
+            .value_name("METHOD_NAME")
+            .help("Find classes declaring a method with this name")
+            .group("operation"))
+        .arg(Arg::new("extends")
+            .long("extends")
+            .value_name("CLASS")
+            .help("Find classes whose superclass is CLASS (full or simple name)")
+            .group("operation"))
+        .arg(Arg::new("implements")
+            .long("implements")
+            .value_name("INTERFACE")
+            .help("Find classes that implement INTERFACE (full or simple name)")
+            .group("operation"))
+        .arg(Arg::new("transitive")
+            .long("transitive")
+            .help("With --extends/--implements, also report indirect descendants; with --references, indirect dependents")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("annotation")
+            .long("annotation")
+            .value_name("ANNOTATION")
+            .help("Find classes, fields and methods carrying this annotation (full or simple name)")
+            .group("operation"))
+        .arg(Arg::new("descriptor")
+            .long("descriptor")
+            .value_name("DESCRIPTOR")
+            .help("Restrict --method to a JVM descriptor, e.g. (Ljava/lang/String;)V")
+            .requires("method"))
+        .arg(Arg::new("inspect")
+            .long("inspect")
+            .value_name("CLASS_NAME")
+            .help("Print a javap-style summary (superclass, interfaces, fields, methods) of a class")
+            .group("operation"))
+        .arg(Arg::new("manifest")
+            .long("manifest")
+            .value_name("KEY")
+            .help("Report a MANIFEST.MF attribute per JAR (all attributes when KEY is omitted)")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
+        .arg(Arg::new("services")
+            .long("services")
+            .value_name("INTERFACE")
+            .help("List META-INF/services provider registrations (only INTERFACE's when given)")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
+        .arg(Arg::new("licenses")
+            .long("licenses")
+            .help("License inventory: each JAR's LICENSE/NOTICE files with their SPDX identifier")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("property_key")
+            .long("property-key")
+            .value_name("KEY")
+            .help("Print the value of a .properties key (regex matching the whole key) on disk and in JARs")
+            .group("operation"))
+        .arg(Arg::new("bytecode_version")
+            .long("bytecode-version")
+            .help("Report the class file version distribution of each JAR")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("max_java")
+            .long("max-java")
+            .value_name("N")
+            .help("With --bytecode-version, flag classes compiled for a Java release newer than N")
+            .value_parser(clap::value_parser!(u16))
+            .requires("bytecode_version"))
+        .arg(Arg::new("coordinates")
+            .long("coordinates")
+            .help("Report the Maven group:artifact:version embedded in each JAR")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("build_info")
+            .long("build-info")
+            .help("Report the commit, branch and build time of each JAR (git.properties, else manifest Build-Jdk/Build-Time)")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("vuln_check")
+            .long("vuln-check")
+            .value_name("FILE")
+            .help("Flag JARs whose Maven coordinates match FILE (group:artifact:<2.17.1 per line)")
+            .group("operation"))
+        .arg(Arg::new("verify_signatures")
+            .long("verify-signatures")
+            .help("Report each JAR as signed (valid digests), tampered or unsigned")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("verify_checksums")
+            .long("verify-checksums")
+            .value_name("FILE")
+            .help("Check files against a sha256sum/sha1sum/md5sum-style list (paths relative to --dir), reporting mismatches and missing files")
+            .group("operation"))
+        .arg(Arg::new("check_classpath")
+            .long("check-classpath")
+            .help("Report manifest Class-Path entries that do not exist relative to their JAR")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("find_duplicates")
+            .long("find-duplicates")
+            .help("Report classes shipped by more than one JAR (identical or divergent bytes)")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("find_duplicate_resources")
+            .long("find-duplicate-resources")
+            .value_name("GLOB")
+            .help("Report non-class entries (e.g. logback.xml) at the same path in more than one JAR, identical or divergent (only paths matching GLOB when given)")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
+        .arg(Arg::new("split_packages")
+            .long("split-packages")
+            .help("Report packages whose classes are spread over more than one JAR (breaks JPMS modules)")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("module_info")
+            .long("module-info")
+            .value_name("FILTER")
+            .help("Report each JAR's module name, requires, exports and opens (only modules matching FILTER, `requires:<module>` or `exports:<package>`, when given)")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .group("operation"))
+        .arg(Arg::new("shaded")
+            .long("shaded")
+            .help("Report relocated (shaded) packages per JAR, grouped by relocated root")
+            .action(clap::ArgAction::SetTrue)
+            .group("operation"))
+        .arg(Arg::new("shade_prefix")
+            .long("shade-prefix")
+            .value_name("PREFIX")
+            .help("With --shaded, also treat PREFIX (e.g. org.apache.flink.shaded) as a relocation prefix (repeatable)")
+            .action(clap::ArgAction::Append)
+            .requires("shaded"))
+        .arg(Arg::new("pattern_file")
+            .long("pattern-file")
+            .value_name("FILE")
+            .help("Read one pattern per line for --class, --class-contains, --package, --search or --master")
+            .requires("operation"))
+        .arg(Arg::new("queries")
+            .long("queries")
+            .value_name("FILE")
+            .help("Run many searches from FILE (one `type pattern` per line) in a single pass")
+            .group("operation"))
+        .group(ArgGroup::new("operation")
+            .multiple(false))
         .arg(Arg::new("directory")
             .short('d')
             .long("dir")
             .value_name("DIR")
-            .help("Directory to search in")
+            .help("Directory to search in ('-' reads the file list from stdin)")
             .required(false)
             .default_value("."))
+        .arg(Arg::new("git_rev")
+            .long("git-rev")
+            .value_name("REF")
+            .help("With --search/--master, scan the blobs of a committed revision of the repository at --dir")
+            .conflicts_with("stdin"))
+        .arg(Arg::new("replace")
+            .long("replace")
+            .value_name("REPLACEMENT")
+            .help("With --search/--master, rewrite matches in text files on disk ($1/${name} insert groups); archives are never modified")
+            .conflicts_with_all(["git_rev", "watch"]))
+        .arg(Arg::new("no_backup")
+            .long("no-backup")
+            .help("With --replace, do not keep the original of each rewritten file as <file>.bak")
+            .requires("replace")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("follow_symlinks")
+            .long("follow-symlinks")
+            .help("Follow symbolic links while walking directories (cycles are skipped)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("stdin")
+            .long("stdin")
+            .help("Process the newline-separated paths read from stdin instead of walking --dir")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("url")
+            .long("url")
+            .value_name("URL")
+            .help("Download the archive at this http(s) URL and search it instead of walking --dir (repeatable; kept under --cache if given)")
+            .action(clap::ArgAction::Append)
+            .conflicts_with_all(["stdin", "git_rev", "watch"]))
+        .arg(Arg::new("url_file")
+            .long("url-file")
+            .value_name("FILE")
+            .help("Like --url, for every URL listed in FILE (one per line)")
+            .conflicts_with_all(["stdin", "git_rev", "watch"]))
         .arg(Arg::new("exclude")
             .short('e')
             .long("exclude")
             .value_name("PATH")
             .help("Exclude files/paths containing this string (can be used multiple times)")
             .action(clap::ArgAction::Append))
+        .arg(Arg::new("exclude_regex")
+            .long("exclude-regex")
+            .value_name("REGEX")
+            .help("Exclude paths matching this regex, e.g. '-sources\\.jar$' (can be used multiple times)")
+            .allow_hyphen_values(true)
+            .action(clap::ArgAction::Append))
+        .arg(Arg::new("type_map")
+            .long("type-map")
+            .value_name("EXT=LABEL")
+            .help("Report files with this extension under a custom match type, e.g. 'tf=terraform' (can be used multiple times)")
+            .value_parser(parse_type_mapping)
+            .action(clap::ArgAction::Append))
+        .arg(Arg::new("include")
+            .long("include")
+            .value_name("GLOB")
+            .help("Only search files matching this glob, e.g. '*.yaml' (can be used multiple times)")
+            .action(clap::ArgAction::Append))
+        .arg(Arg::new("entry_glob")
+            .long("entry-glob")
+            .value_name("GLOB")
+            .help("Only search archive entries whose full name matches, e.g. '**/application.yml' or 'META-INF/**' (repeatable)")
+            .action(clap::ArgAction::Append))
+        .arg(Arg::new("respect_gitignore")
+            .long("respect-gitignore")
+            .help("Skip files ignored by .gitignore, .ignore and global git excludes")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("exclude_hidden")
+            .long("exclude-hidden")
+            .help("Skip files and directories whose name starts with '.' (.git, .idea, ...) while walking")
+            .action(clap::ArgAction::SetTrue)
+            .overrides_with("include_hidden"))
+        .arg(Arg::new("include_hidden")
+            .long("include-hidden")
+            .help("Walk into hidden files and directories (the default; overrides an earlier --exclude-hidden)")
+            .action(clap::ArgAction::SetTrue)
+            .overrides_with("exclude_hidden"))
+        .arg(Arg::new("max_depth")
+            .long("max-depth")
+            .value_name("N")
+            .help("Descend at most N directory levels while walking (1 = only files directly in --dir)")
+            .value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..)))
+        .arg(Arg::new("ignore_case")
+            .short('i')
+            .long("ignore-case")
+            .help("Case-insensitive class name and content matching")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("word")
+            .short('w')
+            .long("word")
+            .help("Match whole words only (whole package/class segments for --class-contains)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("invert_match")
+            .long("invert-match")
+            .help("Report text lines that do NOT match (with --count: how many; with --mini: files with any)")
+            .action(clap::ArgAction::SetTrue))
         .arg(Arg::new("mini")
             .long("mini")
+            .visible_alias("files-with-matches")
             .help("Mini mode: show only unique file names (one per file)")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("files_without_matches")
+            .long("files-without-matches")
+            .help("List the scanned files that had no match instead of the matches")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("null_data")
+            .short('0')
+            .long("null")
+            .help("With --mini or --files-without-matches, end each path with a NUL byte (for xargs -0)")
+            .conflicts_with_all(["count", "summary", "stream"])
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("config")
+            .long("config")
+            .value_name("FILE")
+            .help("Read option defaults from FILE instead of ./jartool.toml"))
         .arg(Arg::new("verbose")
             .short('v')
             .long("verbose")
             .help("Enable verbose output")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("color")
+            .long("color")
+            .value_name("WHEN")
+            .help("Colorize output: auto (terminal only, off when NO_COLOR is set), always or never")
+            .value_parser(["auto", "always", "never"])
+            .default_value("auto"))
+        .arg(Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .help("Print only the results: no status banners, progress bars or statistics")
+            .action(clap::ArgAction::SetTrue))
         .arg(Arg::new("size_threshold")
             .long("min-size")
             .value_name("BYTES")
             .help("Minimum file size to process")
+            .value_parser(clap::value_parser!(u64))
             .default_value("0"))
+        .arg(Arg::new("modified_after")
+            .long("modified-after")
+            .value_name("DATE")
+            .help("Only process files modified at or after DATE: 2024-05-01, 2024-05-01T12:00:00Z, or an age like 7d, 12h")
+            .value_parser(parse_time_spec))
+        .arg(Arg::new("modified_before")
+            .long("modified-before")
+            .value_name("DATE")
+            .help("Only process files modified before DATE (same forms as --modified-after)")
+            .value_parser(parse_time_spec))
         .arg(Arg::new("jobs")
             .short('j')
             .long("jobs")
+            .visible_alias("threads")
             .value_name("N")
-            .help("Number of parallel jobs"))
+            .help("Number of parallel jobs (0 = one per CPU core)")
+            .value_parser(clap::value_parser!(usize)))
         .arg(Arg::new("export")
             .long("export")
             .value_name("FILE")
-            .help("Export results to CSV file"))
+            .help("Export results to FILE (CSV unless --output-format says otherwise)"))
+        .arg(Arg::new("no_csv_sanitize")
+            .long("no-csv-sanitize")
+            .help("Write CSV cells verbatim, without guarding against spreadsheet formulas")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("export_html")
+            .long("export-html")
+            .value_name("FILE")
+            .help("Also write a self-contained HTML report to FILE"))
+        .arg(Arg::new("export_dot")
+            .long("export-dot")
+            .value_name("FILE")
+            .help("With --references, also write the dependency graph to FILE in Graphviz DOT format"))
+        .arg(Arg::new("export_sarif")
+            .long("export-sarif")
+            .value_name("FILE")
+            .help("Also write a SARIF 2.1.0 log to FILE for code-scanning tools"))
+        .arg(Arg::new("stats_json")
+            .long("stats-json")
+            .value_name("FILE")
+            .help("Also write the statistics (with files/classes per second) as JSON to FILE ('-' for stdout)"))
+        .arg(Arg::new("output_format")
+            .long("output-format")
+            .value_name("FORMAT")
+            .help("Result format: text, json, jsonl (one object per line, written as found) or csv (json/jsonl/csv go to stdout unless --export is given)")
+            .value_parser(["text", "json", "jsonl", "csv"])
+            .default_value("text"))
+        .arg(Arg::new("sort")
+            .long("sort")
+            .value_name("BY")
+            .help("Sort results before output: path, type or line (default: discovery order)")
+            .value_parser(["path", "type", "line"]))
+        .arg(Arg::new("dedupe")
+            .long("dedupe")
+            .help("Drop results repeating the same file, line and content (unlike --mini, distinct lines are kept)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("max_nesting")
+            .long("max-nesting")
+            .value_name("DEPTH")
+            .help("Maximum depth to recurse into nested archives (JAR-in-WAR, etc.)")
+            .value_parser(clap::value_parser!(usize))
+            .default_value("2"))
+        .arg(Arg::new("accurate_class_names")
+            .long("accurate-class-names")
+            .help("Read class names from the class file header instead of the entry path (slower)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("string_constants")
+            .long("string-constants")
+            .help("Match class files against their string literals only, not names or descriptors")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("no_trim")
+            .long("no-trim")
+            .help("Keep matched lines verbatim, leading indentation included, instead of trimming them")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("min_string_len")
+            .long("min-string-len")
+            .value_name("N")
+            .help("Shortest printable run extracted from class and binary files")
+            .value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..))
+            .default_value("4"))
+        .arg(Arg::new("context")
+            .long("context")
+            .value_name("N")
+            .help("Show N lines of context around text matches")
+            .value_parser(clap::value_parser!(usize))
+            .default_value("0"))
+        .arg(Arg::new("before_context")
+            .short('B')
+            .long("before-context")
+            .value_name("N")
+            .help("Show N lines before each text match (overrides --context for that side)")
+            .value_parser(clap::value_parser!(usize)))
+        .arg(Arg::new("after_context")
+            .short('A')
+            .long("after-context")
+            .value_name("N")
+            .help("Show N lines after each text match (overrides --context for that side)")
+            .value_parser(clap::value_parser!(usize)))
+        .arg(Arg::new("no_progress")
+            .long("no-progress")
+            .help("Disable the progress bar (it is also hidden when stdout is not a terminal)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("dry_run")
+            .long("dry-run")
+            .help("With --master, show what would be scanned (per-type counts; files with --verbose) and exit")
+            .requires("master")
+            .conflicts_with("output_format")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("bomb_ratio")
+            .long("bomb-ratio")
+            .value_name("RATIO")
+            .help("With --list, flag JARs whose uncompressed/compressed ratio exceeds RATIO")
+            .value_parser(clap::value_parser!(f64))
+            .default_value("100"))
+        .arg(Arg::new("filter_command")
+            .long("filter-command")
+            .value_name("CMD")
+            .help("Keep only results for which CMD (run through the shell, matched line on stdin) exits 0; one process per match")
+            .action(clap::ArgAction::Set))
+        .arg(Arg::new("min_line")
+            .long("min-line")
+            .value_name("N")
+            .help("Drop text matches before line N (results without a line number are kept)")
+            .value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..)))
+        .arg(Arg::new("max_line")
+            .long("max-line")
+            .value_name("N")
+            .help("Drop text matches after line N (results without a line number are kept)")
+            .value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..)))
+        .arg(Arg::new("max_entries")
+            .long("max-entries")
+            .value_name("N")
+            .help("Skip (with a warning) any archive listing more than N entries")
+            .value_parser(clap::value_parser!(usize)))
+        .arg(Arg::new("zip_password")
+            .long("zip-password")
+            .value_name("PASSWORD")
+            .help("Decrypt encrypted archive entries with PASSWORD (otherwise they are reported as encrypted_entry)")
+            .action(clap::ArgAction::Set))
+        .arg(Arg::new("cache")
+            .long("cache")
+            .value_name("DIR")
+            .help("Cache each JAR's class list in DIR; class searches skip JARs unchanged (size and mtime) since")
+            .action(clap::ArgAction::Set))
+        .arg(Arg::new("timeout_per_file")
+            .long("timeout-per-file")
+            .value_name("SECS")
+            .help("Abandon any file that takes longer than SECS to read (warns and continues)")
+            .value_parser(clap::value_parser!(u64).range(1..)))
+        .arg(Arg::new("mmap")
+            .long("mmap")
+            .help("Memory-map JAR/ZIP files instead of reading them (falls back to reads if mapping fails); an archive truncated mid-scan crashes the process")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("stream")
+            .long("stream")
+            .help("Print each result as soon as it is found instead of after the scan")
+            .conflicts_with_all(["sort", "dedupe", "count", "summary", "output_format", "files_without_matches"])
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("max_results")
+            .long("max-results")
+            .value_name("N")
+            .help("Stop scanning new files once N results are found (parallel runs may overshoot slightly)")
+            .value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..)))
+        .arg(Arg::new("first_per_archive")
+            .long("first-per-archive")
+            .help("Stop searching each archive at its first result (faster than --mini, which still scans everything)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("max_line_length")
+            .long("max-line-length")
+            .value_name("N")
+            .help("Cut matched lines longer than N characters when printing and exporting, marking the cut (0 = no limit)")
+            .value_parser(clap::value_parser!(usize))
+            .default_value("500"))
+        .arg(Arg::new("no_truncate")
+            .long("no-truncate")
+            .help("Keep matched lines whole in every output, e.g. for full JSON exports")
+            .conflicts_with("max_line_length")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("pick")
+            .long("pick")
+            .help("After the search, fuzzy-filter the results in the terminal and print the chosen one in full (plain listing when not a TTY)")
+            .conflicts_with_all(["count", "summary", "stream", "output_format", "null_data"])
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("summary")
+            .long("summary")
+            .help("Print a histogram of matches per match type instead of listing every result")
+            .conflicts_with("count")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("count")
+            .long("count")
+            .help("Print only the number of matches (unique files with --mini)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("exit_code")
+            .long("exit-code")
+            .help("grep-style exit status: 0 if anything matched, 1 if nothing did, 2 on error")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("watch")
+            .long("watch")
+            .help("Re-run the search whenever files under --dir change (Ctrl-C to stop)")
+            .conflicts_with("stdin")
+            .action(clap::ArgAction::SetTrue))
         .arg(Arg::new("list_jars")
             .long("list")
             .help("List JAR files and their contents")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("checksums")
+            .long("checksums")
+            .value_name("ALGO")
+            .help("With --list, add a column with each JAR's digest")
+            .value_parser(["md5", "sha1", "sha256"])
+            .requires("list_jars"))
         .get_matches();
 
+    if matches.get_flag("watch") {
+        return watch(&matches);
+    }
+
+    let exit_code = matches.get_flag("exit_code");
+
+    match run(&matches) {
+        Ok(Some(0)) if exit_code => ExitCode::from(1),
+        Ok(_) => ExitCode::SUCCESS,
+        // Same status clap uses for usage errors
+        Err(e) => {
+            eprintln!("{} {}", "ERROR".red(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+// Changes arriving within this window of each other are handled as one rebuild
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+// --watch: run, then re-run after every debounced batch of changes under --dir.
+// Each run builds a fresh JarTool, so no results carry over. Runs until killed.
+fn watch(matches: &ArgMatches) -> ExitCode {
+    let Some(dir) = matches.get_one::<String>("directory").map(Path::new) else {
+        return ExitCode::from(2);
+    };
+    let (sender, events) = mpsc::channel();
+    let watcher = notify::recommended_watcher(sender).and_then(|mut watcher| {
+        // Watched canonically so event paths compare equal to output_paths()
+        let watch_dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        watcher.watch(&watch_dir, RecursiveMode::Recursive).map(|_| watcher)
+    });
+    // Dropping the watcher stops the events, so it lives as long as the loop
+    let _watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("{} cannot watch {}: {}", "ERROR".red(), dir.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let clear_screen = std::io::stdout().is_terminal();
+    loop {
+        if let Err(e) = run(matches) {
+            eprintln!("{} {}", "ERROR".red(), e);
+        }
+        eprintln!("{} Watching {} for changes (Ctrl-C to stop)", "WATCH".purple(), dir.display());
+
+        // Wait for a real change, then for things to settle (a build rewriting
+        // target/ produces a burst of events)
+        let outputs = output_paths(matches);
+        let mut changed = false;
+        loop {
+            let event = if changed { events.recv_timeout(WATCH_DEBOUNCE).ok() } else { events.recv().ok() };
+            match event {
+                Some(Ok(event)) => changed |= is_change(&event, &outputs),
+                Some(Err(e)) => eprintln!("{} watch error: {}", "WARNING".yellow(), e),
+                None if changed => break,
+                None => return ExitCode::SUCCESS,
+            }
+        }
+
+        if clear_screen {
+            print!("\x1B[2J\x1B[H");
+        }
+        eprintln!("{} Change detected, re-running", "WATCH".purple());
+    }
+}
+
+// Reads (including the scan's own) and writes to our export files are not changes
+fn is_change(event: &notify::Event, outputs: &[PathBuf]) -> bool {
+    !matches!(event.kind, EventKind::Access(_)) && event.paths.iter().any(|path| !outputs.iter().any(|output| path.starts_with(output)))
+}
+
+// Absolute paths of the files (and --cache directory) this run writes, as the
+// watcher reports them
+fn output_paths(matches: &ArgMatches) -> Vec<PathBuf> {
+    ["export", "export_html", "export_sarif", "export_dot", "stats_json", "cache"]
+        .iter()
+        .filter_map(|id| matches.get_one::<String>(id))
+        .filter(|file| file.as_str() != "-")
+        .filter_map(|file| std::path::absolute(file).ok())
+        .map(|path| match (path.parent().and_then(|dir| dir.canonicalize().ok()), path.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => path,
+        })
+        .collect()
+}
+
+// Runs the requested operation and returns the number of matches, or `None`
+// when nothing was searched (--list, no operation given)
+fn run(matches: &ArgMatches) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    // Precedence: command line, then jartool.toml, then the built-in defaults
+    let config = Config::discover(matches.get_one::<String>("config").map(Path::new))?;
+
+    let color = if from_command_line(matches, "color") {
+        matches.get_one::<String>("color").map(String::as_str)
+    } else {
+        config.color.as_deref()
+    };
+    let use_color = match color.unwrap_or("auto") {
+        "always" => true,
+        "never" => false,
+        "auto" => std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal(),
+        other => return Err(format!("invalid color `{}` in config (expected auto, always or never)", other).into()),
+    };
+    colored::control::set_override(use_color);
+
     let verbose = matches.get_flag("verbose");
+    let quiet = matches.get_flag("quiet");
+    let stream = matches.get_flag("stream");
     let mini_mode = matches.get_flag("mini");
-    let size_threshold: u64 = matches.get_one::<String>("size_threshold")
-        .unwrap()
-        .parse()
-        .unwrap_or(0);
-    let parallel_jobs = matches.get_one::<String>("jobs")
-        .and_then(|s| s.parse().ok());
-    let search_dir = Path::new(matches.get_one::<String>("directory").unwrap());
+    let size_threshold: u64 = if from_command_line(matches, "size_threshold") {
+        *required::<u64>(matches, "size_threshold")?
+    } else {
+        config.min_size.unwrap_or(0)
+    };
+    let parallel_jobs = matches.get_one::<usize>("jobs").copied().or(config.jobs);
+    let modified_after = matches.get_one::<SystemTime>("modified_after").copied();
+    let modified_before = matches.get_one::<SystemTime>("modified_before").copied();
+    if let (Some(after), Some(before)) = (modified_after, modified_before) {
+        if after >= before {
+            return Err("--modified-after must be earlier than --modified-before".into());
+        }
+    }
+    let min_line = matches.get_one::<usize>("min_line").copied();
+    let max_line = matches.get_one::<usize>("max_line").copied();
+    if let (Some(min), Some(max)) = (min_line, max_line) {
+        if min > max {
+            return Err("--min-line must not be greater than --max-line".into());
+        }
+    }
+    let search_dir = Path::new(required::<String>(matches, "directory")?);
+    let from_stdin = matches.get_flag("stdin") || search_dir == Path::new("-");
+
+    let mut urls: Vec<String> = matches.get_many::<String>("url").unwrap_or_default().cloned().collect();
+    if let Some(file) = matches.get_one::<String>("url_file") {
+        urls.extend(remote::read_url_file(Path::new(file))?);
+    }
+    if let Some(url) = urls.iter().find(|url| !remote::is_url(url)) {
+        return Err(format!("not an http(s) URL: {}", url).into());
+    }
+
+    if !from_stdin && urls.is_empty() {
+        std::fs::metadata(search_dir).map_err(|e| format!("{}: {}", search_dir.display(), e))?;
+    }
     
     // Collect exclusion patterns
-    let excludes: Vec<String> = matches.get_many::<String>("exclude")
+    // Command-line exclusions replace the config file's list rather than extending it
+    let excludes: Vec<String> = if from_command_line(matches, "exclude") {
+        matches.get_many::<String>("exclude").unwrap_or_default().map(|s| s.to_string()).collect()
+    } else {
+        config.exclude
+    };
+
+    if !excludes.is_empty() && !quiet {
+        eprintln!("{} Exclusions: {:?}", "INFO".blue(), excludes);
+    }
+
+    let exclude_regexes: Vec<String> = matches.get_many::<String>("exclude_regex")
         .unwrap_or_default()
         .map(|s| s.to_string())
         .collect();
-
-    if !excludes.is_empty() {
-        println!("{} Exclusions: {:?}", "INFO".blue(), excludes);
+    for pattern in &exclude_regexes {
+        check_regex(pattern)?;
+    }
+    if !exclude_regexes.is_empty() && !quiet {
+        eprintln!("{} Exclusion regexes: {:?}", "INFO".blue(), exclude_regexes);
     }
     
-    if mini_mode {
-        println!("{} Mini mode enabled: showing unique files only", "MODE".purple());
+    if mini_mode && !quiet {
+        eprintln!("{} Mini mode enabled: showing unique files only", "MODE".purple());
+    }
+
+    // Only bare path listings can be NUL-separated
+    let null_data = matches.get_flag("null_data");
+    if null_data && !mini_mode && !matches.get_flag("files_without_matches") {
+        eprintln!("{} --null only applies to --mini and --files-without-matches; ignoring it", "WARNING".yellow());
+    }
+
+    let max_nesting = *required::<usize>(matches, "max_nesting")?;
+
+    // Unlike --exclude, --type-map entries are layered over the file's, extension by extension
+    let type_map = config
+        .type_map
+        .into_iter()
+        .chain(matches.get_many::<(String, String)>("type_map").unwrap_or_default().cloned());
+
+    // --context sets both sides; --before-context/--after-context override one
+    let context = *required::<usize>(matches, "context")?;
+    let context_before = matches.get_one::<usize>("before_context").copied().unwrap_or(context);
+    let context_after = matches.get_one::<usize>("after_context").copied().unwrap_or(context);
+
+    let includes: Vec<String> = matches.get_many::<String>("include")
+        .unwrap_or_default()
+        .map(|s| s.to_string())
+        .collect();
+
+    if !includes.is_empty() && !quiet {
+        eprintln!("{} Includes: {:?}", "INFO".blue(), includes);
     }
 
-    let tool = JarTool::new(verbose, size_threshold, parallel_jobs, excludes, mini_mode);
+    let entry_globs: Vec<String> = matches.get_many::<String>("entry_glob").unwrap_or_default().cloned().collect();
+    if !entry_globs.is_empty() && !quiet {
+        eprintln!("{} Archive entries: {:?}", "INFO".blue(), entry_globs);
+    }
+
+    // Removes temporary downloads when the run ends, however it ends
+    let downloads = if urls.is_empty() {
+        None
+    } else {
+        Some(remote::DownloadDir::new(matches.get_one::<String>("cache").map(Path::new))?)
+    };
+    let mut display_names = Vec::new();
+
+    let file_list = if from_stdin {
+        let files = read_file_list(std::io::stdin().lock())?;
+        if !quiet {
+            eprintln!("{} Read {} paths from stdin", "INFO".blue(), files.len());
+        }
+        Some(files)
+    } else if let Some(downloads) = &downloads {
+        for url in &urls {
+            match downloads.fetch(url) {
+                Ok((path, cached)) => {
+                    if !quiet {
+                        let action = if cached { "Using cached download of" } else { "Downloaded" };
+                        eprintln!("{} {} {}", "INFO".blue(), action, url);
+                    }
+                    display_names.push((path, url.clone()));
+                }
+                Err(e) => eprintln!("{} Skipping {}", "WARNING".yellow(), e),
+            }
+        }
+        if display_names.is_empty() {
+            return Err("none of the URLs could be downloaded".into());
+        }
+        Some(display_names.iter().map(|(path, _)| path.clone()).collect())
+    } else {
+        None
+    };
+
+    // JSON lines are written while searching, so they cannot be sorted or
+    // deduplicated afterwards; --count keeps stdout to itself
+    let json_lines = if matches.get_one::<String>("output_format").is_some_and(|format| format == "jsonl") {
+        if matches.contains_id("sort") || matches.get_flag("dedupe") {
+            return Err("--output-format jsonl writes results as they are found and cannot be combined with --sort or --dedupe".into());
+        }
+        let export_file = matches.get_one::<String>("export").map(String::as_str);
+        let stdout_taken = matches.get_flag("count") || matches.get_flag("files_without_matches");
+        export_file.or((!stdout_taken).then_some("-"))
+    } else {
+        None
+    };
+
+    let tool = JarTool::new(verbose, size_threshold, parallel_jobs, excludes, mini_mode)?
+        .with_includes(&includes)?
+        .with_entry_globs(&entry_globs)?
+        .with_exclude_regexes(&exclude_regexes)?
+        .with_respect_gitignore(matches.get_flag("respect_gitignore"))
+        .with_follow_symlinks(matches.get_flag("follow_symlinks"))
+        .with_include_hidden(!matches.get_flag("exclude_hidden"))
+        .with_max_depth(matches.get_one::<usize>("max_depth").copied())
+        .with_file_list(file_list)
+        .with_display_names(display_names)
+        .with_csv_sanitize(!matches.get_flag("no_csv_sanitize"))
+        .with_max_nesting(max_nesting)
+        .with_accurate_class_names(matches.get_flag("accurate_class_names"))
+        .with_ignore_case(matches.get_flag("ignore_case"))
+        .with_invert_match(matches.get_flag("invert_match"))
+        .with_whole_word(matches.get_flag("word"))
+        .with_type_map(type_map)
+        .with_modified_window(modified_after, modified_before)
+        .with_string_constants(matches.get_flag("string_constants"))
+        .with_trim(!matches.get_flag("no_trim"))
+        .with_min_string_len(*required::<usize>(matches, "min_string_len")?)
+        .with_context_lines(context_before, context_after)
+        .with_max_results(matches.get_one::<usize>("max_results").copied())
+        .with_first_per_archive(matches.get_flag("first_per_archive"))
+        .with_max_line_length(Some(*required::<usize>(matches, "max_line_length")?).filter(|&n| n > 0 && !matches.get_flag("no_truncate")))
+        .with_bomb_ratio(*required::<f64>(matches, "bomb_ratio")?)
+        .with_list_checksums(matches.get_one::<String>("checksums").map(|name| ChecksumAlgorithm::parse(name)).transpose()?)
+        .with_max_entries(matches.get_one::<usize>("max_entries").copied())
+        .with_zip_password(matches.get_one::<String>("zip_password").cloned())
+        .with_filter_command(matches.get_one::<String>("filter_command").cloned())
+        .with_line_range(min_line, max_line)
+        .with_cache(matches.get_one::<String>("cache").map(Path::new))?
+        .with_timeout_per_file(matches.get_one::<u64>("timeout_per_file").map(|secs| Duration::from_secs(*secs)))
+        .with_mmap(matches.get_flag("mmap"))
+        .with_quiet(quiet)
+        .with_stream(stream)
+        .with_json_lines(json_lines)?
+        // Bars redrawing on the terminal would tear streamed result lines
+        .with_progress(!quiet && !stream && json_lines != Some("-") && !matches.get_flag("no_progress") && std::io::stdout().is_terminal());
 
     // Handle list command first
     if matches.get_flag("list_jars") {
         tool.list_jars(search_dir)?;
-        return Ok(());
+        return Ok(None);
+    }
+
+    if matches.get_flag("dry_run") {
+        tool.master_dry_run(search_dir)?;
+        return Ok(None);
     }
 
     let mut operation_performed = false;
 
+    // Modes that take a pattern may leave it out only when --pattern-file supplies them
+    // (arg id, long flag, batch query type)
+    let pattern_modes = [
+        ("exact_class", "class", "class"),
+        ("class_substring", "class-contains", "class-contains"),
+        ("package", "package", "package"),
+        ("content", "search", "content"),
+        ("master", "master", "master"),
+    ];
+    let pattern_file = matches.get_one::<String>("pattern_file");
+    let selected_mode = pattern_modes
+        .iter()
+        .find_map(|(id, flag, query_type)| matches.get_one::<String>(id).map(|value| (*flag, *query_type, value)));
+
+    if let Some((flag, _, value)) = selected_mode {
+        match (value.is_empty(), pattern_file.is_some()) {
+            (true, false) => return Err(format!("--{} needs a pattern (or use --pattern-file)", flag).into()),
+            (false, true) => return Err("give either a pattern or --pattern-file, not both".into()),
+            _ => {}
+        }
+    }
+
+    // Check regexes before scanning so a typo is reported as such, not as a failed search
+    let regex_patterns = match (selected_mode, pattern_file) {
+        (Some((_, "content" | "master", _)), Some(pattern_file)) => read_pattern_file(Path::new(pattern_file))?,
+        (Some((_, "content" | "master", value)), None) => vec![value.clone()],
+        _ => ["class_regex", "property_key"]
+            .iter()
+            .filter_map(|id| matches.get_one::<String>(id).cloned())
+            .collect(),
+    };
+    for pattern in &regex_patterns {
+        check_regex(pattern)?;
+    }
+
     // Handle search operations
-    if let Some(class_name) = matches.get_one::<String>("exact_class") {
+    if let Some(replacement) = matches.get_one::<String>("replace") {
+        if !matches!(selected_mode, Some((_, "content" | "master", _))) {
+            return Err("--replace works with --search or --master".into());
+        }
+        tool.replace_in_files(&regex_patterns, replacement, search_dir, !matches.get_flag("no_backup"))?;
+        operation_performed = true;
+    } else if let Some(rev) = matches.get_one::<String>("git_rev") {
+        let all_files = match selected_mode {
+            Some((_, "content", _)) => false,
+            Some((_, "master", _)) => true,
+            _ => return Err("--git-rev works with --search or --master".into()),
+        };
+        tool.search_git_revision(&regex_patterns, search_dir, rev, all_files)?;
+        operation_performed = true;
+    } else if let Some(pattern_file) = pattern_file {
+        let pattern_file = Path::new(pattern_file);
+        match selected_mode {
+            Some((_, "content", _)) => {
+                tool.search_content_patterns(&regex_patterns, search_dir, &["*"])?;
+            }
+            Some((_, "master", _)) => {
+                tool.master_search_patterns(&regex_patterns, search_dir)?;
+            }
+            Some((_, query_type, _)) => {
+                let queries = BatchQuery::load_patterns(pattern_file, query_type, matches.get_flag("ignore_case"))?;
+                tool.run_queries(&queries, search_dir)?;
+            }
+            None => return Err("--pattern-file works with --class, --class-contains, --package, --search or --master".into()),
+        }
+        operation_performed = true;
+    } else if let Some(class_name) = matches.get_one::<String>("exact_class") {
         tool.search_exact_class(class_name, search_dir)?;
         operation_performed = true;
     } else if let Some(substring) = matches.get_one::<String>("class_substring") {
         tool.search_class_substring(substring, search_dir)?;
         operation_performed = true;
+    } else if let Some(pattern) = matches.get_one::<String>("class_regex") {
+        tool.search_class_regex(pattern, search_dir)?;
+        operation_performed = true;
     } else if let Some(package) = matches.get_one::<String>("package") {
         tool.search_package(package, search_dir)?;
         operation_performed = true;
@@ -1116,21 +978,348 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     } else if let Some(pattern) = matches.get_one::<String>("master") {
         tool.master_search(pattern, search_dir)?;
         operation_performed = true;
+    } else if let Some(target) = matches.get_one::<String>("references") {
+        tool.search_references(target, matches.get_flag("transitive"), search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("reflection") {
+        let symbols = match matches.get_one::<String>("reflection_symbols") {
+            Some(file) => ReflectionSymbol::load(Path::new(file))?,
+            None => ReflectionSymbol::defaults(),
+        };
+        tool.search_reflection(&symbols, search_dir)?;
+        operation_performed = true;
+    } else if let Some(method_name) = matches.get_one::<String>("method") {
+        let descriptor = matches.get_one::<String>("descriptor").map(String::as_str);
+        tool.search_method(method_name, descriptor, search_dir)?;
+        operation_performed = true;
+    } else if let Some(class_name) = matches.get_one::<String>("extends") {
+        tool.search_extends(class_name, matches.get_flag("transitive"), search_dir)?;
+        operation_performed = true;
+    } else if let Some(interface) = matches.get_one::<String>("implements") {
+        tool.search_implements(interface, matches.get_flag("transitive"), search_dir)?;
+        operation_performed = true;
+    } else if let Some(annotation) = matches.get_one::<String>("annotation") {
+        tool.search_annotation(annotation, search_dir)?;
+        operation_performed = true;
+    } else if let Some(class_name) = matches.get_one::<String>("inspect") {
+        tool.inspect_class(class_name, search_dir)?;
+        operation_performed = true;
+    } else if let Some(key) = matches.get_one::<String>("manifest") {
+        let key = Some(key.as_str()).filter(|k| !k.is_empty());
+        tool.search_manifest(key, search_dir)?;
+        operation_performed = true;
+    } else if let Some(interface) = matches.get_one::<String>("services") {
+        let interface = Some(interface.as_str()).filter(|i| !i.is_empty());
+        tool.search_services(interface, search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("licenses") {
+        tool.search_licenses(search_dir)?;
+        operation_performed = true;
+    } else if let Some(key) = matches.get_one::<String>("property_key") {
+        tool.search_property_key(key, search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("bytecode_version") {
+        let max_java = matches.get_one::<u16>("max_java").copied();
+        tool.search_bytecode_versions(max_java, search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("coordinates") {
+        tool.search_coordinates(search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("build_info") {
+        tool.search_build_info(search_dir)?;
+        operation_performed = true;
+    } else if let Some(advisory_file) = matches.get_one::<String>("vuln_check") {
+        let advisories = Advisory::load(Path::new(advisory_file))?;
+        tool.check_vulnerabilities(&advisories, search_dir)?;
+        operation_performed = true;
+    } else if let Some(checksum_file) = matches.get_one::<String>("verify_checksums") {
+        let expected = ExpectedChecksum::load(Path::new(checksum_file))?;
+        tool.verify_checksums(&expected, search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("check_classpath") {
+        tool.check_classpath(search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("verify_signatures") {
+        tool.verify_signatures(search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("split_packages") {
+        tool.find_split_packages(search_dir)?;
+        operation_performed = true;
+    } else if let Some(filter) = matches.get_one::<String>("module_info") {
+        let filter = Some(filter.as_str()).filter(|f| !f.is_empty()).map(ModuleFilter::parse).transpose()?;
+        tool.search_module_info(filter.as_ref(), search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("shaded") {
+        let prefixes: Vec<String> = matches.get_many::<String>("shade_prefix").unwrap_or_default().cloned().collect();
+        tool.search_shaded(&prefixes, search_dir)?;
+        operation_performed = true;
+    } else if let Some(pattern) = matches.get_one::<String>("find_duplicate_resources") {
+        let pattern = Some(pattern.as_str()).filter(|p| !p.is_empty());
+        tool.find_duplicate_resources(pattern, search_dir)?;
+        operation_performed = true;
+    } else if matches.get_flag("find_duplicates") {
+        tool.find_duplicate_classes(search_dir)?;
+        operation_performed = true;
+    } else if let Some(query_file) = matches.get_one::<String>("queries") {
+        let queries = BatchQuery::load(Path::new(query_file))?;
+        tool.run_queries(&queries, search_dir)?;
+        operation_performed = true;
     }
 
     if !operation_performed {
-        println!("{} No search operation specified. Use --help for options.", "ERROR".red());
-        return Ok(());
+        eprintln!("{} No search operation specified. Use --help for options.", "ERROR".red());
+        return Ok(None);
+    }
+
+    tool.save_cache()?;
+
+    if tool.result_limit_reached() && !quiet {
+        eprintln!("{} Stopped early after reaching --max-results", "INFO".blue());
+    }
+
+    if matches.get_flag("dedupe") {
+        let removed = tool.dedupe_results()?;
+        if removed > 0 && !quiet {
+            eprintln!("{} Removed {} duplicate results", "INFO".blue(), removed);
+        }
+    }
+
+    if let Some(by) = matches.get_one::<String>("sort") {
+        tool.sort_results(by)?;
+    }
+
+    let output_format = matches.get_one::<String>("output_format").map(String::as_str).unwrap_or("text");
+    let export_file = matches.get_one::<String>("export").map(String::as_str);
+
+    let count_only = matches.get_flag("count");
+
+    if matches.get_flag("files_without_matches") {
+        let unmatched = tool.files_without_matches();
+        if count_only {
+            println!("{}", unmatched.len());
+        } else if null_data {
+            write_null_separated(unmatched.iter().map(String::as_str))?;
+        } else {
+            for path in &unmatched {
+                println!("{}", path);
+            }
+        }
+        return Ok(Some(unmatched.len()));
     }
 
-    // Print results
-    tool.print_results();
-    tool.print_stats();
+    // Print results, unless a machine-readable format is taking over stdout
+    if count_only {
+        println!("{}", tool.result_count());
+    } else if null_data && mini_mode && output_format == "text" {
+        // Nothing but the paths, so the statistics stay off stdout too
+        let results = tool.results();
+        write_null_separated(results.iter().map(|r| r.file_location.as_str()))?;
+    } else if matches.get_flag("pick") && interactive() && tool.result_count() > 0 {
+        pick_result(&tool.results())?;
+    } else if output_format == "text" || export_file.is_some() {
+        // Streamed results are already on screen
+        if matches.get_flag("summary") {
+            tool.print_summary();
+        } else if !stream {
+            tool.print_results();
+        }
+        if !quiet {
+            tool.print_stats();
+        }
+    }
 
     // Export if requested
-    if let Some(export_file) = matches.get_one::<String>("export") {
-        tool.export_csv(export_file)?;
+    match (output_format, export_file) {
+        // --count owns stdout
+        (_, None) if count_only => {}
+        ("json", file) => tool.export_json(file.unwrap_or("-"))?,
+        ("jsonl", file) => tool.finish_json_lines(file.unwrap_or("-"))?,
+        ("csv", file) => tool.export_csv(file.unwrap_or("-"))?,
+        (_, Some(file)) => tool.export_csv(file)?,
+        _ => {}
     }
 
+    if let Some(file) = matches.get_one::<String>("export_html") {
+        tool.export_html(file)?;
+    }
+
+    if let Some(file) = matches.get_one::<String>("export_dot") {
+        tool.export_dot(file)?;
+    }
+
+    if let Some(file) = matches.get_one::<String>("export_sarif") {
+        tool.export_sarif(file)?;
+    }
+
+    if let Some(file) = matches.get_one::<String>("stats_json") {
+        tool.export_stats_json(file)?;
+    }
+
+    Ok(Some(tool.result_count()))
+}
+
+// --pick draws on stderr and reads keys from the terminal
+fn interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal() && std::io::stderr().is_terminal()
+}
+
+// Longest content shown per line in the --pick list; the chosen result prints in full
+const PICK_LINE_WIDTH: usize = 120;
+
+// --pick: one line per result to filter on, then every field of the chosen one.
+// Escape leaves without printing anything.
+fn pick_result(results: &[SearchResult]) -> Result<(), Box<dyn std::error::Error>> {
+    let items: Vec<String> = results
+        .iter()
+        .map(|r| {
+            let position = match (r.line_number, r.byte_offset) {
+                (Some(line), _) => format!(":{}", line),
+                (None, Some(offset)) => format!("@0x{:X}", offset),
+                (None, None) => String::new(),
+            };
+            let content: String = r.line_content.chars().take(PICK_LINE_WIDTH).collect();
+            format!("{}{}  [{}] {}", r.file_location, position, r.match_type, content)
+        })
+        .collect();
+
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("{} results, type to filter", results.len()))
+        .items(&items)
+        .default(0)
+        .max_length(20)
+        .interact_opt()?;
+    let Some(result) = selection.and_then(|index| results.get(index)) else {
+        return Ok(());
+    };
+
+    println!("{:<10} {}", "Location:".cyan(), result.file_location.green());
+    if let Some(archive) = &result.archive_path {
+        println!("{:<10} {}", "Archive:".cyan(), archive);
+        println!("{:<10} {}", "Entry:".cyan(), result.entry_path);
+    }
+    if let Some(line) = result.line_number {
+        println!("{:<10} {}", "Line:".cyan(), line.to_string().yellow());
+    }
+    if let Some(offset) = result.byte_offset {
+        println!("{:<10} {}", "Offset:".cyan(), format!("0x{:X}", offset).yellow());
+    }
+    println!("{:<10} {}", "Type:".cyan(), result.match_type.purple());
+    if let Some(query) = &result.query {
+        println!("{:<10} {}", "Query:".cyan(), query.blue());
+    }
+    println!("{:<10} {}", "Content:".cyan(), result.line_content);
+    if let (Some(context), Some(start)) = (&result.context, result.context_start) {
+        println!("{}", "Context:".cyan());
+        for (offset, line) in context.iter().enumerate() {
+            println!("  {:>6}  {}", (start + offset).to_string().dimmed(), line);
+        }
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+// --null: paths may contain spaces or newlines, but never NUL
+fn write_null_separated<'a>(paths: impl Iterator<Item = &'a str>) -> std::io::Result<()> {
+    let mut out = std::io::stdout().lock();
+    for path in paths {
+        out.write_all(path.as_bytes())?;
+        out.write_all(b"\0")?;
+    }
+    out.flush()
+}
+
+// Whether the user typed the option, as opposed to clap filling in its default
+fn from_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+// Options with a default always carry a value; a missing one is reported
+// rather than panicking
+fn required<'a, T: Any + Clone + Send + Sync>(matches: &'a ArgMatches, id: &str) -> Result<&'a T, Box<dyn std::error::Error>> {
+    matches.try_get_one::<T>(id)?.ok_or_else(|| format!("missing value for {}", id).into())
+}
+
+// Accepts an age (`30m`, `12h`, `7d`, `2w`, counted back from now), an RFC 3339
+// timestamp, or a local date / date and time
+fn parse_time_spec(value: &str) -> Result<SystemTime, String> {
+    use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeDelta};
+
+    let value = value.trim();
+    if let Some(unit) = value.chars().last().filter(|c| "smhdw".contains(*c)) {
+        if let Ok(amount) = value[..value.len() - 1].parse::<i64>() {
+            let age = match unit {
+                's' => TimeDelta::try_seconds(amount),
+                'm' => TimeDelta::try_minutes(amount),
+                'h' => TimeDelta::try_hours(amount),
+                'd' => TimeDelta::try_days(amount),
+                _ => TimeDelta::try_weeks(amount),
+            };
+            return age
+                .and_then(|age| Local::now().checked_sub_signed(age))
+                .map(SystemTime::from)
+                .ok_or_else(|| format!("age `{}` is out of range", value));
+        }
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.into());
+    }
+    let local = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)));
+
+    local
+        .and_then(|time| time.and_local_timezone(Local).earliest())
+        .map(SystemTime::from)
+        .ok_or_else(|| format!("expected a date like 2024-05-01 or 2024-05-01T12:00:00Z, or an age like 7d, got `{}`", value))
+}
+
+// Splits `--type-map ext=label`
+fn parse_type_mapping(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((ext, label)) if !ext.trim_start_matches('.').is_empty() && !label.is_empty() => {
+            Ok((ext.to_string(), label.to_string()))
+        }
+        _ => Err(format!("expected EXT=LABEL, e.g. tf=terraform, got `{}`", value)),
+    }
+}
+
+// Turns a regex syntax error into "invalid regex `p`: reason", the parser's
+// caret line pointing at the problem, and a hint for the usual mistakes
+fn check_regex(pattern: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let Err(error) = regex::Regex::new(pattern) else {
+        return Ok(());
+    };
+
+    let detail = match &error {
+        regex::Error::Syntax(text) => text.clone(),
+        other => other.to_string(),
+    };
+    // Syntax errors read "regex parse error:", the pattern with a caret, then "error: <reason>"
+    let reason = detail.lines().last().and_then(|l| l.strip_prefix("error: ")).unwrap_or(&detail);
+    let mut message = format!("invalid regex `{}`: {}", pattern, reason);
+    for line in detail.lines().skip(1).filter(|l| !l.starts_with("error: ")) {
+        message.push('\n');
+        message.push_str(line);
+    }
+    if let Some(hint) = regex_hint(reason) {
+        message.push_str(&format!("\n  hint: {}", hint));
+    }
+    Err(message.into())
+}
+
+fn regex_hint(reason: &str) -> Option<&'static str> {
+    const HINTS: &[(&str, &str)] = &[
+        ("unclosed group", r"did you mean to escape `(`? Use `\(` to match a literal parenthesis"),
+        ("unopened group", r"did you mean to escape `)`? Use `\)` to match a literal parenthesis"),
+        ("unclosed character class", r"did you mean to escape `[`? Use `\[` to match a literal bracket"),
+        ("repetition operator missing expression", r"`*`, `+` and `?` repeat what precedes them; escape them (`\*`) to match literally, or use `.*` as a wildcard"),
+        ("counted repetition", r"did you mean to escape `{`? Use `\{` to match a literal brace"),
+        ("repetition quantifier", r"did you mean to escape `{`? Use `\{` to match a literal brace"),
+        ("decimal literal", r"did you mean to escape `{`? Use `\{` to match a literal brace"),
+        ("incomplete escape sequence", r"the pattern ends in a lone `\`; use `\\` to match a backslash"),
+        ("unrecognized escape sequence", r"use `\\` to match a literal backslash"),
+        ("not supported", "backreferences and look-around are not available; match the text itself instead"),
+    ];
+    HINTS.iter().find(|(needle, _)| reason.contains(needle)).map(|(_, hint)| *hint)
+}
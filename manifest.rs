@@ -0,0 +1,157 @@
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+pub const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+#[derive(Debug, Clone, Default)]
+pub struct ManifestSection {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub main_attributes: Vec<(String, String)>,
+    // Per-entry sections introduced by `Name:` (used by signed JARs)
+    pub sections: Vec<ManifestSection>,
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Manifest {
+        let mut manifest = Manifest::default();
+        let mut current: Vec<(String, String)> = Vec::new();
+        let mut in_main = true;
+
+        for line in unfold_lines(text) {
+            if line.is_empty() {
+                manifest.finish_section(&mut current, &mut in_main);
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                current.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        manifest.finish_section(&mut current, &mut in_main);
+
+        manifest
+    }
+
+    fn finish_section(&mut self, current: &mut Vec<(String, String)>, in_main: &mut bool) {
+        if current.is_empty() {
+            return;
+        }
+
+        let attributes = std::mem::take(current);
+        if *in_main {
+            self.main_attributes = attributes;
+            *in_main = false;
+        } else {
+            let name = attributes
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("Name"))
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default();
+            self.sections.push(ManifestSection { name, attributes });
+        }
+    }
+
+    /// Looks up a main attribute; attribute names are case-insensitive.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.main_attributes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The entries of the `Class-Path` attribute: space-separated relative
+    /// URLs, returned with `%XX` escapes decoded.
+    pub fn class_path(&self) -> Vec<String> {
+        self.get("Class-Path")
+            .map(|value| value.split_whitespace().map(percent_decode).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn read_from_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<Manifest> {
+        let mut entry = archive.by_name(MANIFEST_PATH).ok()?;
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).ok()?;
+        Some(Manifest::parse(&String::from_utf8_lossy(&bytes)))
+    }
+}
+
+// `lib/my%20lib.jar` -> `lib/my lib.jar`; malformed escapes are kept as is
+fn percent_decode(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Joins continuation lines: the spec wraps at 72 bytes and starts each
+// continuation with a single space
+fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix(' '), lines.last_mut()) {
+            (Some(continuation), Some(previous)) if !previous.is_empty() => previous.push_str(continuation),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folded_value_is_joined() {
+        let text = "Manifest-Version: 1.0\nClass-Path: lib/first.jar lib/second.jar lib/third.jar lib/fourt\n h.jar lib/my%20lib.jar\nMain-Class: com.app.Main\n";
+        let manifest = Manifest::parse(text);
+
+        assert_eq!(manifest.class_path(), vec!["lib/first.jar", "lib/second.jar", "lib/third.jar", "lib/fourth.jar", "lib/my lib.jar"]);
+        assert_eq!(manifest.get("main-class"), Some("com.app.Main"));
+    }
+
+    #[test]
+    fn line_of_exactly_72_bytes_is_not_continued() {
+        let line = format!("Implementation-Title: {}", "x".repeat(72 - "Implementation-Title: ".len()));
+        assert_eq!(line.len(), 72);
+        let manifest = Manifest::parse(&format!("{}\nImplementation-Version: 2.1\n", line));
+
+        assert_eq!(manifest.get("Implementation-Title").map(str::len), Some(50));
+        assert_eq!(manifest.get("Implementation-Version"), Some("2.1"));
+    }
+
+    #[test]
+    fn crlf_input_splits_main_and_entry_sections() {
+        let text = "Manifest-Version: 1.0\r\nCreated-By: Ma\r\n ven\r\n\r\nName: com/app/Main.class\r\nSHA-256-Digest: abc=\r\n\r\nName: com/app/Util.class\r\nSHA-256-Digest: def=\r\n";
+        let manifest = Manifest::parse(text);
+
+        assert_eq!(manifest.get("Created-By"), Some("Maven"));
+        assert_eq!(manifest.main_attributes.len(), 2);
+        let names: Vec<&str> = manifest.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["com/app/Main.class", "com/app/Util.class"]);
+        assert_eq!(manifest.sections[1].attributes[1], ("SHA-256-Digest".to_string(), "def=".to_string()));
+    }
+}
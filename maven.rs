@@ -0,0 +1,377 @@
+use crate::JarToolError;
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+// Maven writes build metadata under META-INF/maven/<groupId>/<artifactId>/
+const MAVEN_METADATA_DIR: &str = "META-INF/maven/";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MavenCoordinate {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+}
+
+impl fmt::Display for MavenCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group_id, self.artifact_id, self.version)
+    }
+}
+
+impl MavenCoordinate {
+    /// Reads `groupId`, `artifactId` and `version` from a `pom.properties` file.
+    pub fn from_pom_properties(text: &str) -> Option<MavenCoordinate> {
+        let mut group_id = None;
+        let mut artifact_id = None;
+        let mut version = None;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once(['=', ':']) {
+                let value = Some(value.trim().to_string());
+                match key.trim() {
+                    "groupId" => group_id = value,
+                    "artifactId" => artifact_id = value,
+                    "version" => version = value,
+                    _ => {}
+                }
+            }
+        }
+
+        Some(MavenCoordinate { group_id: group_id?, artifact_id: artifact_id?, version: version? })
+    }
+
+    /// Reads the project's coordinates from a `pom.xml`, inheriting `groupId`
+    /// and `version` from `<parent>` when the project does not declare them.
+    pub fn from_pom_xml(text: &str) -> Option<MavenCoordinate> {
+        let mut project = PomFields::default();
+        let mut parent = PomFields::default();
+        let mut path: Vec<String> = Vec::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find('<') {
+            let text_before = &rest[..start];
+            rest = &rest[start..];
+
+            if let Some(field) = path.last() {
+                let fields = match path.len() {
+                    2 => Some(&mut project),
+                    3 if path[1] == "parent" => Some(&mut parent),
+                    _ => None,
+                };
+                if let Some(fields) = fields {
+                    fields.set(field, text_before.trim());
+                }
+            }
+
+            // Comments, declarations and processing instructions carry no structure
+            let skip_to = if rest.starts_with("<!--") {
+                "-->"
+            } else if rest.starts_with("<?") {
+                "?>"
+            } else if rest.starts_with("<!") {
+                ">"
+            } else {
+                ""
+            };
+            if !skip_to.is_empty() {
+                let end = rest.find(skip_to)? + skip_to.len();
+                rest = &rest[end..];
+                continue;
+            }
+
+            let end = rest.find('>')?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                if path.last().map(String::as_str) == Some(name.trim()) {
+                    path.pop();
+                }
+            } else if !tag.ends_with('/') {
+                let name = tag.split_whitespace().next().unwrap_or_default();
+                path.push(name.to_string());
+            }
+        }
+
+        Some(MavenCoordinate {
+            group_id: project.group_id.or(parent.group_id)?,
+            artifact_id: project.artifact_id?,
+            version: project.version.or(parent.version)?,
+        })
+    }
+
+    /// Collects the coordinates embedded in an archive, one per
+    /// `META-INF/maven/<group>/<artifact>/` directory (fat JARs carry several).
+    /// `pom.properties` is preferred; `pom.xml` is the fallback.
+    pub fn read_from_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Vec<MavenCoordinate> {
+        let mut directories: Vec<String> = archive
+            .file_names()
+            .filter(|name| name.starts_with(MAVEN_METADATA_DIR))
+            .filter(|name| name.ends_with("/pom.properties") || name.ends_with("/pom.xml"))
+            .filter_map(|name| name.rsplit_once('/').map(|(dir, _)| dir.to_string()))
+            .collect();
+        directories.sort();
+        directories.dedup();
+
+        let mut coordinates = Vec::new();
+        for dir in directories {
+            let coordinate = read_entry(archive, &format!("{}/pom.properties", dir))
+                .and_then(|text| MavenCoordinate::from_pom_properties(&text))
+                .or_else(|| {
+                    read_entry(archive, &format!("{}/pom.xml", dir))
+                        .and_then(|text| MavenCoordinate::from_pom_xml(&text))
+                });
+            coordinates.extend(coordinate);
+        }
+
+        coordinates
+    }
+}
+
+#[derive(Default)]
+struct PomFields {
+    group_id: Option<String>,
+    artifact_id: Option<String>,
+    version: Option<String>,
+}
+
+impl PomFields {
+    fn set(&mut self, element: &str, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+        let slot = match element {
+            "groupId" => &mut self.group_id,
+            "artifactId" => &mut self.artifact_id,
+            "version" => &mut self.version,
+            _ => return,
+        };
+        slot.get_or_insert_with(|| value.to_string());
+    }
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+#[derive(Debug, Clone)]
+struct VersionConstraint {
+    comparison: Comparison,
+    version: String,
+}
+
+impl VersionConstraint {
+    fn parse(text: &str) -> VersionConstraint {
+        let text = text.trim();
+        let (comparison, version) = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("==", Comparison::Equal),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ]
+        .iter()
+        .find_map(|(op, comparison)| text.strip_prefix(op).map(|v| (*comparison, v)))
+        .unwrap_or((Comparison::Equal, text));
+
+        VersionConstraint { comparison, version: version.trim().to_string() }
+    }
+
+    fn matches(&self, version: &str) -> bool {
+        let ordering = compare_versions(version, &self.version);
+        match self.comparison {
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+            Comparison::Equal => ordering.is_eq(),
+        }
+    }
+}
+
+/// A known-bad dependency from a `--vuln-check` list: `group:artifact:VERSIONS`
+/// where VERSIONS is an exact version or comma-separated constraints such as
+/// `>=2.0,<2.17.1`, optionally followed by a free-text label (e.g. a CVE id).
+/// The first whitespace ends VERSIONS, so a range must not contain spaces.
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub versions: String,
+    pub label: Option<String>,
+    constraints: Vec<VersionConstraint>,
+}
+
+impl Advisory {
+    pub fn parse(line: &str) -> Result<Self, JarToolError> {
+        let (spec, label) = match line.trim().split_once(char::is_whitespace) {
+            Some((spec, label)) => (spec, Some(label.trim().to_string())),
+            None => (line.trim(), None),
+        };
+
+        let mut parts = spec.splitn(3, ':');
+        let (group_id, artifact_id, versions) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(g), Some(a), Some(v)) if !g.is_empty() && !a.is_empty() && !v.is_empty() => (g, a, v),
+            _ => return Err(JarToolError::InvalidArgument(format!("Invalid advisory '{}': expected group:artifact:version", line.trim()))),
+        };
+        // `>=2.0, <2.17.1` would otherwise become `>=2.0,` with the label `<2.17.1`
+        if versions.ends_with(',') || label.as_deref().is_some_and(|l| l.starts_with(['<', '>', '='])) {
+            return Err(JarToolError::InvalidArgument(format!("Invalid advisory '{}': no spaces allowed in a version range", line.trim())));
+        }
+
+        Ok(Advisory {
+            group_id: group_id.to_string(),
+            artifact_id: artifact_id.to_string(),
+            versions: versions.to_string(),
+            label,
+            constraints: versions.split(',').map(VersionConstraint::parse).collect(),
+        })
+    }
+
+    /// Loads an advisory list, skipping blank lines and `#` comments.
+    pub fn load(path: &std::path::Path) -> Result<Vec<Self>, JarToolError> {
+        let text = std::fs::read_to_string(path).map_err(|e| JarToolError::io(path, e))?;
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Advisory::parse)
+            .collect()
+    }
+
+    pub fn matches(&self, coordinate: &MavenCoordinate) -> bool {
+        self.group_id == coordinate.group_id
+            && self.artifact_id == coordinate.artifact_id
+            && self.constraints.iter().all(|c| c.matches(&coordinate.version))
+    }
+}
+
+impl fmt::Display for Advisory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group_id, self.artifact_id, self.versions)?;
+        if let Some(label) = &self.label {
+            write!(f, " ({})", label)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares Maven-style versions segment by segment: numeric segments
+/// numerically, qualifiers textually, and a release above its qualifiers
+/// (`2.0` > `2.0-beta1`, `2.17.1` > `2.9`). As in Maven, `final`, `ga` and
+/// `release` mark the release itself (`2.0.Final` == `2.0`).
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let segments = |v: &str| -> Vec<String> {
+        v.split(['.', '-', '_'])
+            .filter(|s| !s.is_empty())
+            .map(str::to_lowercase)
+            .filter(|s| !RELEASE_QUALIFIERS.contains(&s.as_str()))
+            .collect()
+    };
+    let (a, b) = (segments(a), segments(b));
+
+    for i in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(i), b.get(i)) {
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(_), Err(_)) => Ordering::Greater,
+                (Err(_), Ok(_)) => Ordering::Less,
+                (Err(_), Err(_)) => x.cmp(y),
+            },
+            (Some(x), None) => trailing_segment_order(x),
+            (None, Some(y)) => trailing_segment_order(y).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+// Qualifiers Maven's ComparableVersion treats as the bare release
+const RELEASE_QUALIFIERS: &[&str] = &["final", "ga", "release"];
+
+// How a version with an extra segment compares to one that stops early:
+// `1.0.0` == `1.0`, `1.0.1` > `1.0`, `1.0-rc1` < `1.0`
+fn trailing_segment_order(segment: &str) -> Ordering {
+    match segment.parse::<u64>() {
+        Ok(0) => Ordering::Equal,
+        Ok(_) => Ordering::Greater,
+        Err(_) => Ordering::Less,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_qualifiers_equal_the_bare_version() {
+        assert_eq!(compare_versions("2.0.Final", "2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("5.3.RELEASE", "5.3.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2-GA", "1.2"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0.Final", "2.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("2.0.Final", "2.0-beta1"), Ordering::Greater);
+    }
+
+    fn coordinate(version: &str) -> MavenCoordinate {
+        MavenCoordinate {
+            group_id: "org.apache.logging.log4j".to_string(),
+            artifact_id: "log4j-core".to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    fn affected(advisory: &str, versions: &[&str]) -> Vec<bool> {
+        let advisory = Advisory::parse(advisory).unwrap();
+        versions.iter().map(|v| advisory.matches(&coordinate(v))).collect()
+    }
+
+    #[test]
+    fn range_includes_lower_and_excludes_upper_bound() {
+        let advisory = "org.apache.logging.log4j:log4j-core:>=2.0,<2.17.1 CVE-2021-44228";
+        assert_eq!(affected(advisory, &["1.2.17", "2.0", "2.9", "2.17.0", "2.17.1", "2.18"]), vec![false, true, true, true, false, false]);
+        assert_eq!(Advisory::parse(advisory).unwrap().label.as_deref(), Some("CVE-2021-44228"));
+    }
+
+    #[test]
+    fn single_constraints() {
+        let versions = ["2.14", "2.15", "2.15.1"];
+        assert_eq!(affected("org.apache.logging.log4j:log4j-core:<=2.15", &versions), vec![true, true, false]);
+        assert_eq!(affected("org.apache.logging.log4j:log4j-core:=2.15", &versions), vec![false, true, false]);
+        assert_eq!(affected("org.apache.logging.log4j:log4j-core:2.15", &versions), vec![false, true, false]);
+        assert_eq!(affected("org.apache.logging.log4j:log4j-core:>2.15", &versions), vec![false, false, true]);
+    }
+
+    #[test]
+    fn other_artifacts_never_match() {
+        let advisory = Advisory::parse("org.apache.logging.log4j:log4j-api:<2.17.1").unwrap();
+        assert!(!advisory.matches(&coordinate("2.0")));
+    }
+
+    #[test]
+    fn space_inside_range_is_rejected() {
+        assert!(Advisory::parse("org.apache.logging.log4j:log4j-core:>=2.0, <2.17.1").is_err());
+        assert!(Advisory::parse("org.apache.logging.log4j:log4j-core:>=2.0 <2.17.1").is_err());
+    }
+}
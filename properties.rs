@@ -0,0 +1,137 @@
+/// One key/value pair from a `.properties` file, with the line it starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+/// Parses `.properties` text the way `java.util.Properties.load` does: `#`/`!`
+/// comments, `=`, `:` or whitespace separators, backslash line continuations
+/// and escapes (`\t`, `\n`, `\uXXXX`, ...).
+pub fn parse(text: &str) -> Vec<Property> {
+    let mut properties = Vec::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+            continue;
+        }
+
+        // Join continuation lines: an odd number of trailing backslashes
+        let mut logical = String::from(trimmed);
+        while ends_with_continuation(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+
+        let (key, value) = split_key_value(&logical);
+        properties.push(Property { key: unescape(key), value: unescape(value), line: index + 1 });
+    }
+
+    properties
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1
+}
+
+// The key ends at the first unescaped `=`, `:` or whitespace; whitespace around
+// a single `=`/`:` separator belongs to neither side
+fn split_key_value(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    let mut key_end = line.len();
+
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            key_end = i;
+            break;
+        }
+    }
+
+    let rest = line[key_end..].trim_start();
+    let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest).trim_start();
+    (&line[..key_end], rest)
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\u{c}'),
+            Some('u') => {
+                let hex: String = chars.clone().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(decoded) if hex.len() == 4 => {
+                        result.push(decoded);
+                        chars.nth(3);
+                    }
+                    // Malformed escapes are kept as written rather than rejected
+                    _ => result.push_str("\\u"),
+                }
+            }
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(text: &str) -> Vec<(String, String, usize)> {
+        parse(text).into_iter().map(|p| (p.key, p.value, p.line)).collect()
+    }
+
+    fn pair(key: &str, value: &str, line: usize) -> (String, String, usize) {
+        (key.to_string(), value.to_string(), line)
+    }
+
+    #[test]
+    fn backslash_continues_the_line() {
+        let text = "# fruits\nfruits = apple, \\\n         banana, \\\n    cherry\npath=C:\\\\temp\\\\\nnext=1\n";
+        assert_eq!(
+            pairs(text),
+            vec![pair("fruits", "apple, banana, cherry", 2), pair("path", "C:\\temp\\", 5), pair("next", "1", 6)]
+        );
+    }
+
+    #[test]
+    fn colon_and_whitespace_separate_keys() {
+        let text = "a:1\nb : 2\nc 3\nd\t  4\n! comment\ne\nkey\\ with\\:colon = v\n";
+        assert_eq!(
+            pairs(text),
+            vec![pair("a", "1", 1), pair("b", "2", 2), pair("c", "3", 3), pair("d", "4", 4), pair("e", "", 6), pair("key with:colon", "v", 7)]
+        );
+    }
+
+    #[test]
+    fn unicode_and_control_escapes() {
+        let text = "greeting=caf\\u00e9\\tok\nbroken=\\u12g4\nshort=\\u12\n";
+        assert_eq!(
+            pairs(text),
+            vec![pair("greeting", "café\tok", 1), pair("broken", "\\u12g4", 2), pair("short", "\\u12", 3)]
+        );
+    }
+}
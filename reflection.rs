@@ -0,0 +1,113 @@
+use crate::JarToolError;
+use crate::classfile::{self, ClassFile};
+use std::fmt;
+use std::path::Path;
+
+/// APIs watched by `--reflection` unless `--reflection-symbols` replaces them.
+pub const DEFAULT_SYMBOLS: &[&str] = &[
+    "java.lang.Class#forName",
+    "java.lang.Class#newInstance",
+    "java.lang.Class#getMethod",
+    "java.lang.Class#getDeclaredMethod",
+    "java.lang.Class#getDeclaredMethods",
+    "java.lang.Class#getField",
+    "java.lang.Class#getDeclaredField",
+    "java.lang.Class#getDeclaredFields",
+    "java.lang.Class#getConstructor",
+    "java.lang.Class#getDeclaredConstructor",
+    "java.lang.ClassLoader#loadClass",
+    "java.lang.ClassLoader#defineClass",
+    "java.lang.reflect.Method#invoke",
+    "java.lang.reflect.Constructor#newInstance",
+    "java.lang.reflect.Field#get",
+    "java.lang.reflect.Field#set",
+    "java.lang.reflect.AccessibleObject#setAccessible",
+    "java.lang.reflect.Method#setAccessible",
+    "java.lang.reflect.Field#setAccessible",
+    "java.lang.reflect.Constructor#setAccessible",
+    "java.lang.reflect.Proxy#newProxyInstance",
+    "java.lang.invoke.MethodHandles#privateLookupIn",
+    "java.lang.invoke.MethodHandles$Lookup#findVirtual",
+    "java.lang.invoke.MethodHandles$Lookup#findStatic",
+    "java.lang.invoke.MethodHandles$Lookup#findSpecial",
+    "sun.misc.Unsafe",
+    "\"theUnsafe\"",
+];
+
+/// One watched API, written as `pkg.Class#method` (a call to that method),
+/// `pkg.Class` (any reference to the class) or `"text"` (a string literal,
+/// e.g. a member name looked up reflectively).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReflectionSymbol {
+    // Owner in internal form (`java/lang/Class`)
+    Method { owner: String, name: String },
+    Class(String),
+    StringConstant(String),
+}
+
+impl ReflectionSymbol {
+    pub fn parse(text: &str) -> Result<Self, JarToolError> {
+        let text = text.trim();
+        if let Some(literal) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            return Ok(Self::StringConstant(literal.to_string()));
+        }
+        if text.is_empty() || text.contains(char::is_whitespace) || text.starts_with('"') {
+            return Err(JarToolError::InvalidArgument(format!("invalid reflection symbol `{}`: expected pkg.Class#method, pkg.Class or \"text\"", text)));
+        }
+
+        match text.split_once('#') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() => Ok(Self::Method {
+                owner: classfile::to_internal_name(owner),
+                name: name.to_string(),
+            }),
+            Some(_) => Err(JarToolError::InvalidArgument(format!("invalid reflection symbol `{}`: expected pkg.Class#method", text))),
+            None => Ok(Self::Class(classfile::to_internal_name(text))),
+        }
+    }
+
+    pub fn defaults() -> Vec<Self> {
+        DEFAULT_SYMBOLS.iter().filter_map(|symbol| Self::parse(symbol).ok()).collect()
+    }
+
+    /// Loads a symbol list, one per line, skipping blank lines and `#` comments.
+    pub fn load(path: &Path) -> Result<Vec<Self>, JarToolError> {
+        let text = std::fs::read_to_string(path).map_err(|e| JarToolError::io(path, e))?;
+        let symbols = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Self::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if symbols.is_empty() {
+            return Err(JarToolError::InvalidArgument(format!("no reflection symbols found in {}", path.display())));
+        }
+        Ok(symbols)
+    }
+}
+
+impl fmt::Display for ReflectionSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Method { owner, name } => write!(f, "{}#{}", classfile::to_binary_name(owner), name),
+            Self::Class(name) => write!(f, "{}", classfile::to_binary_name(name)),
+            Self::StringConstant(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+/// The symbols `class_file` uses, in the order they were given.
+pub fn find_symbols<'a>(class_file: &ClassFile, symbols: &'a [ReflectionSymbol]) -> Vec<&'a ReflectionSymbol> {
+    let methods = class_file.referenced_methods();
+    let classes = class_file.referenced_classes();
+    let strings = class_file.string_constants();
+
+    symbols
+        .iter()
+        .filter(|symbol| match symbol {
+            ReflectionSymbol::Method { owner, name } => methods.iter().any(|(o, n)| o == owner && n == name),
+            ReflectionSymbol::Class(class) => classes.iter().any(|(c, _)| c == class),
+            ReflectionSymbol::StringConstant(text) => strings.contains(&text.as_str()),
+        })
+        .collect()
+}
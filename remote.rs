@@ -0,0 +1,94 @@
+use crate::JarToolError;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Whether a target names a remote archive rather than a local path.
+pub fn is_url(target: &str) -> bool {
+    let lower = target.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Reads a --url-file: one URL per line, skipping blank lines and `#` comments.
+pub fn read_url_file(path: &Path) -> Result<Vec<String>, JarToolError> {
+    let text = std::fs::read_to_string(path).map_err(|e| JarToolError::io(path, e))?;
+    let mut urls = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !is_url(line) {
+            return Err(JarToolError::InvalidArgument(format!("{}:{}: not an http(s) URL: {}", path.display(), number + 1, line)));
+        }
+        urls.push(line.to_string());
+    }
+    Ok(urls)
+}
+
+/// Where downloaded archives are kept: `downloads/` under the `--cache`
+/// directory, so later runs reuse them, or else a per-process temporary
+/// directory that is removed again when this value is dropped.
+#[derive(Debug)]
+pub struct DownloadDir {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl DownloadDir {
+    pub fn new(cache_dir: Option<&Path>) -> Result<Self, JarToolError> {
+        let (path, temporary) = match cache_dir {
+            Some(dir) => (dir.join("downloads"), false),
+            None => (std::env::temp_dir().join(format!("jartool-{}", std::process::id())), true),
+        };
+        std::fs::create_dir_all(&path).map_err(|e| JarToolError::io(&path, e))?;
+        Ok(Self { path, temporary })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Downloads `url` unless an earlier run already did, returning the local
+    /// copy and whether it was already there. The copy keeps the URL's file
+    /// name, behind a digest of the whole URL, so archive types are still
+    /// recognised by extension. Proxies come from `HTTP_PROXY`, `HTTPS_PROXY`,
+    /// `ALL_PROXY` and `NO_PROXY`.
+    pub fn fetch(&self, url: &str) -> Result<(PathBuf, bool), JarToolError> {
+        let digest = Sha256::digest(url.as_bytes());
+        let prefix: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        let local = self.path.join(format!("{}-{}", prefix, url_file_name(url)));
+        if local.is_file() {
+            return Ok((local, true));
+        }
+
+        let response = ureq::get(url).call().map_err(|e| JarToolError::Download { url: url.to_string(), message: e.to_string() })?;
+
+        // Streamed to disk, so the size of the archive does not matter; written
+        // aside first so an interrupted download is never mistaken for a cached one
+        let partial = local.with_extension("part");
+        let mut file = File::create(&partial).map_err(|e| JarToolError::io(&partial, e))?;
+        std::io::copy(&mut response.into_body().into_reader(), &mut file).map_err(|e| JarToolError::Download { url: url.to_string(), message: e.to_string() })?;
+        std::fs::rename(&partial, &local)?;
+        Ok((local, false))
+    }
+}
+
+impl Drop for DownloadDir {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+// Last path segment without query or fragment, e.g. `guava-33.0.0-jre.jar`
+fn url_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty() && !name.contains(':'))
+        .unwrap_or("download")
+        .to_string()
+}
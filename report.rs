@@ -0,0 +1,290 @@
+use crate::{SearchResult, SearchStats};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+// Findings that point at a concrete problem rather than a plain search hit
+const WARNING_MATCH_TYPES: &[&str] = &[
+    "bytecode_too_new",
+    "checksum_missing",
+    "checksum_mismatch",
+    "duplicate_class_divergent",
+    "duplicate_resource_divergent",
+    "encrypted_entry",
+    "license_missing",
+    "missing_classpath_entry",
+    "signed_invalid",
+    "vulnerable_dependency",
+];
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.5em; }
+table { border-collapse: collapse; margin: 0.5em 0 1em; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f3f3f3; }
+table.results { width: 100%; }
+table.results th { cursor: pointer; user-select: none; }
+table.results th:after { content: " \2195"; color: #aaa; }
+td.content { font-family: Menlo, Consolas, monospace; white-space: pre-wrap; word-break: break-all; }
+details { margin-bottom: 0.5em; }
+summary { cursor: pointer; font-weight: 600; padding: 4px 0; }
+.count { color: #777; font-weight: normal; }
+"#;
+
+// Click a header to sort its table; numeric columns sort numerically
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.results th").forEach(function (th) {
+  th.addEventListener("click", function () {
+    var table = th.closest("table");
+    var body = table.tBodies[0];
+    var index = Array.prototype.indexOf.call(th.parentNode.children, th);
+    var ascending = th.dataset.order !== "asc";
+    th.dataset.order = ascending ? "asc" : "desc";
+    var rows = Array.prototype.slice.call(body.rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[index].textContent, y = b.cells[index].textContent;
+      var nx = parseFloat(x), ny = parseFloat(y);
+      var cmp = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+      return ascending ? cmp : -cmp;
+    });
+    rows.forEach(function (row) { body.appendChild(row); });
+  });
+});
+"#;
+
+/// Writes a self-contained HTML report: a summary table mirroring the
+/// terminal statistics, then one collapsible, sortable table per archive.
+pub fn write_html<W: Write>(out: &mut W, results: &[SearchResult], stats: &SearchStats, parallel_jobs: usize) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>JarTool report</title>")?;
+    writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+    writeln!(out, "<h1>JarTool report</h1>")?;
+
+    let summary = [
+        ("JAR files scanned", stats.total_jars.to_string()),
+        ("ZIP files scanned", stats.total_zip_files.to_string()),
+        ("TAR files scanned", stats.total_tar_files.to_string()),
+        ("Class files found", stats.total_class_files.to_string()),
+        ("Java files found", stats.total_java_files.to_string()),
+        ("Other files found", stats.total_other_files.to_string()),
+        ("Total files processed", stats.files_processed.to_string()),
+        ("Matches found", results.len().to_string()),
+        ("Elapsed time", format!("{:.2}s", stats.elapsed_time.as_secs_f64())),
+        ("Parallel jobs", parallel_jobs.to_string()),
+    ];
+    writeln!(out, "<h2>Summary</h2>\n<table class=\"summary\">")?;
+    for (label, value) in summary {
+        writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", label, escape(&value))?;
+    }
+    writeln!(out, "</table>")?;
+
+    let mut groups: BTreeMap<&str, Vec<&SearchResult>> = BTreeMap::new();
+    for result in results {
+        groups.entry(archive_of(result)).or_default().push(result);
+    }
+
+    writeln!(out, "<h2>Results</h2>")?;
+    if groups.is_empty() {
+        writeln!(out, "<p>No matches found.</p>")?;
+    }
+
+    for (archive, group) in &groups {
+        writeln!(out, "<details open>\n<summary>{} <span class=\"count\">({} results)</span></summary>", escape(archive), group.len())?;
+        writeln!(out, "<table class=\"results\">\n<thead><tr><th>#</th><th>Entry</th><th>Line</th><th>Type</th><th>Query</th><th>Content</th></tr></thead>\n<tbody>")?;
+
+        for (i, result) in group.iter().enumerate() {
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"content\">{}</td></tr>",
+                i + 1,
+                escape(entry_of(result)),
+                result.line_number.map(|n| n.to_string()).unwrap_or_default(),
+                escape(&result.match_type),
+                escape(result.query.as_deref().unwrap_or_default()),
+                escape(&result.line_content),
+            )?;
+        }
+
+        writeln!(out, "</tbody>\n</table>\n</details>")?;
+    }
+
+    writeln!(out, "<script>{}</script>\n</body>\n</html>", SCRIPT)?;
+    Ok(())
+}
+
+/// Writes a SARIF 2.1.0 log for code-scanning tools: one rule per distinct
+/// `match_type`, one result per match. Archive entries are reported against
+/// the archive file, with the entry path as a logical location.
+pub fn write_sarif<W: Write>(out: &mut W, results: &[SearchResult]) -> io::Result<()> {
+    let match_types: BTreeSet<&str> = results.iter().map(|r| r.match_type.as_str()).collect();
+    let rules: Vec<Value> = match_types
+        .iter()
+        .map(|match_type| {
+            json!({
+                "id": match_type,
+                "name": match_type,
+                "shortDescription": { "text": format!("jartool {} match", match_type.replace('_', " ")) },
+                "defaultConfiguration": { "level": sarif_level(match_type) },
+            })
+        })
+        .collect();
+
+    let sarif_results: Vec<Value> = results
+        .iter()
+        .map(|result| {
+            let archive = archive_of(result);
+            let entry = entry_of(result);
+
+            let mut physical = json!({ "artifactLocation": { "uri": sarif_uri(archive) } });
+            if let Some(line) = result.line_number.filter(|&n| n > 0) {
+                physical["region"] = json!({ "startLine": line });
+            }
+
+            let mut location = json!({ "physicalLocation": physical });
+            if !entry.is_empty() {
+                location["logicalLocations"] = json!([{ "fullyQualifiedName": entry, "kind": "module" }]);
+            }
+
+            // Code-scanning UIs only show the archive, so name the entry in the message
+            let message = if entry.is_empty() {
+                result.line_content.clone()
+            } else {
+                format!("{}: {}", entry, result.line_content)
+            };
+
+            let mut sarif_result = json!({
+                "ruleId": result.match_type,
+                "ruleIndex": match_types.iter().position(|t| *t == result.match_type),
+                "level": sarif_level(&result.match_type),
+                "message": { "text": message },
+                "locations": [location],
+            });
+            if let Some(query) = &result.query {
+                sarif_result["properties"] = json!({ "query": query });
+            }
+            sarif_result
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": sarif_results,
+        }],
+    });
+
+    serde_json::to_writer_pretty(&mut *out, &log)?;
+    writeln!(out)
+}
+
+fn sarif_level(match_type: &str) -> &'static str {
+    if WARNING_MATCH_TYPES.contains(&match_type) {
+        "warning"
+    } else {
+        "note"
+    }
+}
+
+/// Writes the reverse-dependency graph of `--references` results in Graphviz
+/// DOT: one edge per `A -> B (kinds)` result, labelled with the reference
+/// kinds, with each referencing class drawn inside a cluster for its archive.
+pub fn write_dot<W: Write>(out: &mut W, results: &[SearchResult]) -> io::Result<()> {
+    let mut clusters: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut edges: Vec<(&str, &str, &str)> = Vec::new();
+
+    for result in results.iter().filter(|r| r.match_type == "reference") {
+        let Some((from, rest)) = result.line_content.split_once(" -> ") else {
+            continue;
+        };
+        let (to, kinds) = match rest.split_once(" (") {
+            Some((to, kinds)) => (to, kinds.trim_end_matches(')')),
+            None => (rest, ""),
+        };
+        clusters.entry(archive_of(result)).or_default().insert(from);
+        edges.push((from, to, kinds));
+    }
+
+    writeln!(out, "digraph references {{")?;
+    writeln!(out, "  rankdir=LR;")?;
+    writeln!(out, "  node [shape=box, fontname=\"Helvetica\"];")?;
+    for (i, (archive, classes)) in clusters.iter().enumerate() {
+        writeln!(out, "  subgraph cluster_{} {{", i)?;
+        writeln!(out, "    label={};", dot_quote(archive))?;
+        for class in classes {
+            writeln!(out, "    {};", dot_quote(class))?;
+        }
+        writeln!(out, "  }}")?;
+    }
+    for (from, to, kinds) in edges {
+        writeln!(out, "  {} -> {} [label={}];", dot_quote(from), dot_quote(to), dot_quote(kinds))?;
+    }
+    writeln!(out, "}}")
+}
+
+fn dot_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Relative paths stay relative so code-scanning UIs resolve them against the
+// repository root; absolute paths become file:// URIs
+fn sarif_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+
+    let mut uri = String::with_capacity(path.len());
+    if path.starts_with('/') {
+        uri.push_str("file://");
+    } else if path.as_bytes().get(1) == Some(&b':') {
+        uri.push_str("file:///");
+    }
+    for c in path.chars() {
+        match c {
+            ' ' => uri.push_str("%20"),
+            '%' => uri.push_str("%25"),
+            '#' => uri.push_str("%23"),
+            '?' => uri.push_str("%3F"),
+            _ => uri.push(c),
+        }
+    }
+    uri
+}
+
+// The on-disk archive a result belongs to; plain files are their own group
+fn archive_of(result: &SearchResult) -> &str {
+    result.archive_path.as_deref().unwrap_or(&result.file_location)
+}
+
+// The entry within that archive, empty for plain files
+fn entry_of(result: &SearchResult) -> &str {
+    if result.archive_path.is_some() {
+        &result.entry_path
+    } else {
+        ""
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,208 @@
+use crate::manifest::{Manifest, MANIFEST_PATH};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+// Signature block formats that can accompany a .SF file
+const SIGNATURE_BLOCK_EXTENSIONS: &[&str] = &["RSA", "DSA", "EC"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Unsigned,
+    // Signer names, i.e. the .SF file base names
+    Valid(Vec<String>),
+    // Why the digests do not add up
+    Invalid(String),
+}
+
+/// Checks a JAR's signature digests the way `jarsigner -verify` does, minus
+/// the certificate check: the .SF file must match the manifest, and every
+/// entry must match its manifest digest. The PKCS#7 block itself is only
+/// required to be present, not cryptographically verified.
+pub fn verify_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> SignatureStatus {
+    let names: Vec<String> = archive.file_names().map(String::from).collect();
+
+    let signature_files: Vec<&String> = names
+        .iter()
+        .filter(|name| is_signature_related(name) && name.to_uppercase().ends_with(".SF"))
+        .collect();
+    if signature_files.is_empty() {
+        return SignatureStatus::Unsigned;
+    }
+
+    let Some(manifest_bytes) = read_bytes(archive, MANIFEST_PATH) else {
+        return SignatureStatus::Invalid("signature files present but no manifest".to_string());
+    };
+    let manifest = Manifest::parse(&String::from_utf8_lossy(&manifest_bytes));
+
+    let mut signers = Vec::new();
+    for sf_name in signature_files {
+        let base = &sf_name[..sf_name.len() - ".SF".len()];
+        let has_block = SIGNATURE_BLOCK_EXTENSIONS
+            .iter()
+            .any(|ext| names.iter().any(|n| n.eq_ignore_ascii_case(&format!("{}.{}", base, ext))));
+        if !has_block {
+            return SignatureStatus::Invalid(format!("{} has no signature block", sf_name));
+        }
+
+        let Some(sf_bytes) = read_bytes(archive, sf_name) else {
+            return SignatureStatus::Invalid(format!("cannot read {}", sf_name));
+        };
+        let signature_file = Manifest::parse(&String::from_utf8_lossy(&sf_bytes));
+        if let Err(reason) = check_signature_file(&signature_file, &manifest_bytes) {
+            return SignatureStatus::Invalid(format!("{}: {}", sf_name, reason));
+        }
+
+        signers.push(base.trim_start_matches("META-INF/").to_string());
+    }
+
+    // Sections without digests only carry metadata (e.g. package sealing)
+    let entry_digests: HashMap<&str, &[(String, String)]> = manifest
+        .sections
+        .iter()
+        .filter(|section| section.attributes.iter().any(|(k, _)| k.to_ascii_lowercase().ends_with("-digest")))
+        .map(|section| (section.name.as_str(), section.attributes.as_slice()))
+        .collect();
+
+    for name in names.iter().filter(|n| !n.ends_with('/') && !is_signature_related(n)) {
+        let Some(attributes) = entry_digests.get(name.as_str()) else {
+            return SignatureStatus::Invalid(format!("{} is not covered by the signature", name));
+        };
+        let Some(bytes) = read_bytes(archive, name) else {
+            return SignatureStatus::Invalid(format!("cannot read {}", name));
+        };
+        if let Err(reason) = check_digest(attributes, "-Digest", &bytes) {
+            return SignatureStatus::Invalid(format!("{}: {}", name, reason));
+        }
+    }
+
+    if let Some(missing) = entry_digests.keys().find(|name| !names.iter().any(|n| n == *name)) {
+        return SignatureStatus::Invalid(format!("{} is signed but missing from the archive", missing));
+    }
+
+    SignatureStatus::Valid(signers)
+}
+
+// The .SF file either digests the whole manifest, or (when the manifest was
+// extended after signing) each manifest section individually
+fn check_signature_file(signature_file: &Manifest, manifest_bytes: &[u8]) -> Result<(), String> {
+    if check_digest(&signature_file.main_attributes, "-Digest-Manifest", manifest_bytes).is_ok() {
+        return Ok(());
+    }
+
+    let sections: HashMap<String, &[u8]> = raw_sections(manifest_bytes)
+        .into_iter()
+        .skip(1)
+        .filter_map(|raw| {
+            let parsed = Manifest::parse(&String::from_utf8_lossy(raw));
+            parsed.get("Name").map(|name| (name.to_string(), raw))
+        })
+        .collect();
+
+    if signature_file.sections.is_empty() {
+        return Err("manifest digest mismatch".to_string());
+    }
+
+    for section in &signature_file.sections {
+        let raw = sections
+            .get(&section.name)
+            .ok_or_else(|| format!("manifest has no section for {}", section.name))?;
+        check_digest(&section.attributes, "-Digest", raw)
+            .map_err(|reason| format!("manifest section {}: {}", section.name, reason))?;
+    }
+
+    Ok(())
+}
+
+// Verifies `bytes` against the first `<ALGORITHM><suffix>` attribute we can compute
+fn check_digest(attributes: &[(String, String)], suffix: &str, bytes: &[u8]) -> Result<(), String> {
+    let mut unsupported = None;
+
+    for (key, expected) in attributes {
+        let Some(algorithm) = key.len().checked_sub(suffix.len()).and_then(|end| key.get(..end)) else {
+            continue;
+        };
+        if !key[algorithm.len()..].eq_ignore_ascii_case(suffix) {
+            continue;
+        }
+
+        match digest(algorithm, bytes) {
+            Some(actual) if BASE64.encode(&actual) == expected.trim() => return Ok(()),
+            Some(_) => return Err(format!("{} digest mismatch", algorithm)),
+            None => unsupported = Some(algorithm),
+        }
+    }
+
+    Err(match unsupported {
+        Some(algorithm) => format!("unsupported digest algorithm {}", algorithm),
+        None => "no digest".to_string(),
+    })
+}
+
+fn digest(algorithm: &str, bytes: &[u8]) -> Option<Vec<u8>> {
+    match algorithm.to_uppercase().as_str() {
+        "SHA1" | "SHA-1" => Some(Sha1::digest(bytes).to_vec()),
+        "SHA-256" => Some(Sha256::digest(bytes).to_vec()),
+        "SHA-384" => Some(Sha384::digest(bytes).to_vec()),
+        "SHA-512" => Some(Sha512::digest(bytes).to_vec()),
+        _ => None,
+    }
+}
+
+// Splits raw manifest bytes into sections, each including its terminating
+// blank line, since that is exactly what the .SF section digests cover
+fn raw_sections(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut sections = Vec::new();
+    let mut section_start = 0;
+    let mut line_start = 0;
+
+    while line_start < bytes.len() {
+        let line_end = bytes[line_start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|i| line_start + i + 1)
+            .unwrap_or(bytes.len());
+        let line = &bytes[line_start..line_end];
+
+        if line == b"\n" || line == b"\r\n" {
+            if line_start > section_start {
+                sections.push(&bytes[section_start..line_end]);
+            }
+            section_start = line_end;
+        }
+        line_start = line_end;
+    }
+
+    if section_start < bytes.len() {
+        sections.push(&bytes[section_start..]);
+    }
+
+    sections
+}
+
+// The manifest and signature files are not themselves covered by the manifest
+fn is_signature_related(name: &str) -> bool {
+    let upper = name.to_uppercase();
+    let Some(file) = upper.strip_prefix("META-INF/") else {
+        return false;
+    };
+    if file.contains('/') {
+        return false;
+    }
+
+    file == "MANIFEST.MF"
+        || file.starts_with("SIG-")
+        || file.ends_with(".SF")
+        || SIGNATURE_BLOCK_EXTENSIONS.iter().any(|ext| file.ends_with(&format!(".{}", ext)))
+}
+
+fn read_bytes<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<Vec<u8>> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}